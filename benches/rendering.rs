@@ -17,11 +17,7 @@ fn create_test_table(num_rows: usize, num_cols: usize) -> TableData {
         })
         .collect();
 
-    TableData {
-        headers,
-        rows,
-        interner,
-    }
+    TableData::new(headers, rows, interner)
 }
 
 /// Benchmark column width calculation (scanning all cells for max width).
@@ -33,6 +33,15 @@ impl ColumnConfig {
         }
     }
 
+    /// Add default (visible, auto-width) columns until there are `num_columns`.
+    /// New columns are appended to the end of the display order.
+    pub fn grow(&mut self, num_columns: usize) {
+        for idx in self.columns.len()..num_columns {
+            self.columns.push(ColumnState::default());
+            self.display_order.push(idx);
+        }
+    }
+
     /// Reset to auto-size for all columns and show all hidden columns
     pub fn reset(&mut self) {
         for col in &mut self.columns {
@@ -168,6 +177,16 @@ mod tests {
         assert_eq!(config.get_width(1), None);
     }
 
+    #[test]
+    fn test_grow_appends_visible_columns() {
+        let mut config = ColumnConfig::new(2);
+        config.hide(0);
+        config.swap_display(0, 1);
+        config.grow(4);
+        assert_eq!(config.visible_indices(), vec![1, 2, 3]);
+        assert!(!config.is_visible(0));
+    }
+
     #[test]
     fn test_out_of_bounds() {
         let config = ColumnConfig::new(2);
@@ -0,0 +1,131 @@
+//! Transparent decompression of gzip and zstd input.
+//!
+//! Compressed input is recognized by its magic number rather than the file
+//! extension, so piped input (`cat export.csv.gz | pte`) is decoded too.
+
+use flate2::bufread::MultiGzDecoder;
+use std::io::{self, BufRead, BufReader};
+
+/// Leading bytes of a gzip member
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Leading bytes of a zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// File extensions of compressed input, stripped to find the inner format
+const COMPRESSED_EXTENSIONS: &[&str] = &["gz", "gzip", "zst", "zstd"];
+
+/// Compression formats that are decoded on the fly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Detect the compression format from the first bytes of input.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if bytes.starts_with(&ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// Check if a file extension names a compression format (`gz`, `zst`).
+pub fn is_compressed_extension(extension: &str) -> bool {
+    COMPRESSED_EXTENSIONS
+        .iter()
+        .any(|ext| extension.eq_ignore_ascii_case(ext))
+}
+
+/// Wrap `reader` in a decoder if its input starts with a gzip or zstd magic number.
+///
+/// Uncompressed input is returned unchanged. Only the buffered bytes are
+/// inspected, nothing is consumed. Concatenated gzip members and zstd frames
+/// are decoded as one stream.
+pub fn decompress<R>(mut reader: R) -> io::Result<Box<dyn BufRead + Send>>
+where
+    R: BufRead + Send + 'static,
+{
+    let compression = Compression::detect(reader.fill_buf()?);
+    Ok(match compression {
+        Some(Compression::Gzip) => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        Some(Compression::Zstd) => Box::new(BufReader::new(
+            zstd::stream::read::Decoder::with_buffer(reader)?,
+        )),
+        None => Box::new(reader),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::{Read, Write};
+
+    const TEXT: &str = "id,name\n1,Alice\n2,Bob\n";
+
+    fn read_all(reader: impl BufRead + Send + 'static) -> io::Result<String> {
+        let mut text = String::new();
+        decompress(reader)?.read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Compression::detect(&gzip(TEXT)), Some(Compression::Gzip));
+        let zstd = zstd::encode_all(TEXT.as_bytes(), 0).unwrap();
+        assert_eq!(Compression::detect(&zstd), Some(Compression::Zstd));
+        assert_eq!(Compression::detect(TEXT.as_bytes()), None);
+        assert_eq!(Compression::detect(&[]), None);
+    }
+
+    #[test]
+    fn test_plain_input_unchanged() {
+        let text = read_all(io::Cursor::new(TEXT.as_bytes().to_vec())).unwrap();
+        assert_eq!(text, TEXT);
+    }
+
+    #[test]
+    fn test_decompress_gzip() {
+        // Two concatenated members, as produced by appending to a .gz file
+        let mut bytes = gzip("id,name\n1,Alice\n");
+        bytes.extend(gzip("2,Bob\n"));
+
+        let text = read_all(io::Cursor::new(bytes)).unwrap();
+        assert_eq!(text, TEXT);
+    }
+
+    #[test]
+    fn test_decompress_zstd() {
+        let bytes = zstd::encode_all(TEXT.as_bytes(), 0).unwrap();
+
+        let text = read_all(io::Cursor::new(bytes)).unwrap();
+        assert_eq!(text, TEXT);
+    }
+
+    #[test]
+    fn test_truncated_gzip_is_an_error() {
+        let bytes = gzip(&TEXT.repeat(100));
+        let truncated = bytes[..bytes.len() / 2].to_vec();
+
+        assert!(read_all(io::Cursor::new(truncated)).is_err());
+    }
+
+    #[test]
+    fn test_is_compressed_extension() {
+        assert!(is_compressed_extension("gz"));
+        assert!(is_compressed_extension("ZST"));
+        assert!(!is_compressed_extension("csv"));
+    }
+}
@@ -0,0 +1,172 @@
+//! CSV/TSV input parsing.
+//!
+//! Parses delimiter-separated text into TableData using the csv crate.
+//! Supports quoted fields, embedded newlines, and custom delimiters.
+//! Rows with fewer or more fields than the header are accepted as-is.
+
+use crate::parser::TableData;
+use lasso::{Rodeo, Spur};
+use std::io::{self, Read};
+
+/// Build a csv reader with the settings used for all delimited input.
+///
+/// The first record is treated as headers and ragged rows are allowed,
+/// matching how psql rows are accepted by the psql parser.
+pub fn delimited_reader<R: Read>(reader: R, delimiter: u8) -> csv::Reader<R> {
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(reader)
+}
+
+/// Convert a raw csv record into owned cell strings.
+///
+/// Invalid UTF-8 is replaced rather than rejected so a single bad byte
+/// does not abort loading the rest of the file.
+pub fn record_to_row(record: &csv::ByteRecord) -> Vec<String> {
+    record
+        .iter()
+        .map(|field| String::from_utf8_lossy(field).into_owned())
+        .collect()
+}
+
+/// Convert a csv error into an io::Error for the streaming loader.
+pub fn csv_error_to_io(err: csv::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Read the header record from a delimited reader.
+///
+/// Returns `None` if the input is empty or the header record is blank.
+pub fn read_delimited_header<R: Read>(
+    reader: &mut csv::Reader<R>,
+) -> io::Result<Option<Vec<String>>> {
+    let headers = reader.byte_headers().map_err(csv_error_to_io)?;
+    let headers: Vec<String> = record_to_row(headers)
+        .into_iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+
+    if headers.is_empty() || headers.iter().all(|h| h.is_empty()) {
+        return Ok(None);
+    }
+
+    Ok(Some(headers))
+}
+
+/// Parse CSV/TSV text into structured TableData.
+///
+/// Expected format (with `,` as the delimiter):
+/// ```text
+/// id,name,notes
+/// 1,Alice,"likes ""quotes"""
+/// 2,Bob,"spans
+/// two lines"
+/// ```
+///
+/// Returns `None` if input is empty or has no header record.
+pub fn parse_delimited(input: &str, delimiter: u8) -> Option<TableData> {
+    let mut reader = delimited_reader(input.as_bytes(), delimiter);
+    let headers = read_delimited_header(&mut reader).ok()??;
+
+    let mut interner = Rodeo::default();
+    let mut rows: Vec<Vec<Spur>> = Vec::new();
+    let mut record = csv::ByteRecord::new();
+
+    while reader.read_byte_record(&mut record).ok()? {
+        let row: Vec<Spur> = record
+            .iter()
+            .map(|field| interner.get_or_intern(String::from_utf8_lossy(field)))
+            .collect();
+        rows.push(row);
+    }
+
+    Some(TableData::new(headers, rows, interner))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve_rows(table: &TableData) -> Vec<Vec<String>> {
+        table
+            .rows
+            .iter()
+            .map(|row| table.resolve_row(row))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_simple_csv() {
+        let input = "id,name,age\n1,Alice,30\n2,Bob,25\n";
+
+        let table = parse_delimited(input, b',').unwrap();
+        assert_eq!(table.headers, vec!["id", "name", "age"]);
+        assert_eq!(
+            resolve_rows(&table),
+            vec![vec!["1", "Alice", "30"], vec!["2", "Bob", "25"]]
+        );
+    }
+
+    #[test]
+    fn test_parse_quoted_fields() {
+        let input = "id,note\n1,\"hello, world\"\n2,\"say \"\"hi\"\"\"\n";
+
+        let table = parse_delimited(input, b',').unwrap();
+        assert_eq!(
+            resolve_rows(&table),
+            vec![vec!["1", "hello, world"], vec!["2", "say \"hi\""]]
+        );
+    }
+
+    #[test]
+    fn test_parse_embedded_newline() {
+        let input = "id,note\n1,\"line one\nline two\"\n2,plain\n";
+
+        let table = parse_delimited(input, b',').unwrap();
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(resolve_rows(&table)[0][1], "line one\nline two");
+    }
+
+    #[test]
+    fn test_parse_tsv() {
+        let input = "id\tname\n1\tAlice, Jr.\n";
+
+        let table = parse_delimited(input, b'\t').unwrap();
+        assert_eq!(table.headers, vec!["id", "name"]);
+        assert_eq!(resolve_rows(&table), vec![vec!["1", "Alice, Jr."]]);
+    }
+
+    #[test]
+    fn test_parse_custom_delimiter() {
+        let input = "a;b\n1;2\n";
+
+        let table = parse_delimited(input, b';').unwrap();
+        assert_eq!(table.headers, vec!["a", "b"]);
+        assert_eq!(resolve_rows(&table), vec![vec!["1", "2"]]);
+    }
+
+    #[test]
+    fn test_parse_ragged_rows() {
+        let input = "a,b,c\n1,2\n1,2,3,4\n";
+
+        let table = parse_delimited(input, b',').unwrap();
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(table.rows[0].len(), 2);
+        assert_eq!(table.rows[1].len(), 4);
+    }
+
+    #[test]
+    fn test_parse_bom_stripped() {
+        let input = "\u{FEFF}id,name\n1,Alice\n";
+
+        let table = parse_delimited(input, b',').unwrap();
+        assert_eq!(table.headers, vec!["id", "name"]);
+    }
+
+    #[test]
+    fn test_parse_empty_input() {
+        assert!(parse_delimited("", b',').is_none());
+    }
+}
@@ -0,0 +1,183 @@
+//! Input format detection for piped data.
+//!
+//! Looks at the first lines of input (the same lines buffered for the
+//! header probe) and guesses which parser should handle the stream.
+
+use crate::delimited;
+use crate::markdown;
+use crate::parser::{self, InputFormat};
+
+/// Guess the input format from the first lines of input.
+///
+/// Checks are ordered from the most to the least distinctive layout:
+/// JSON, psql expanded (`-[ RECORD 1 ]`), psql aligned (header + `---+---`
+/// separator), markdown tables, then delimited formats (TSV, psql unaligned,
+/// CSV) by consistent field counts.
+///
+/// Returns `None` if no format matches.
+pub fn detect_format(lines: &[&str]) -> Option<InputFormat> {
+    let mut non_empty = lines.iter().filter(|line| !line.trim().is_empty());
+    let first = non_empty.next()?.trim();
+
+    if first.starts_with('[') {
+        return Some(InputFormat::Json);
+    }
+    if first.starts_with('{') {
+        // One complete object per line is NDJSON; anything else is pretty-printed JSON
+        return if serde_json::from_str::<serde_json::Value>(first).is_ok() {
+            Some(InputFormat::Ndjson)
+        } else {
+            Some(InputFormat::Json)
+        };
+    }
+
+    if parser::is_expanded_record_header(first) {
+        return Some(InputFormat::PsqlExpanded);
+    }
+
+    let second = non_empty.next().map(|line| line.trim()).unwrap_or("");
+    if is_psql_separator(second) {
+        return Some(InputFormat::Psql);
+    }
+    if markdown::is_markdown_separator(second) {
+        return Some(InputFormat::Markdown);
+    }
+
+    if has_consistent_fields(lines, b'\t') {
+        return Some(InputFormat::Tsv);
+    }
+    if has_consistent_pipes(lines) {
+        return Some(InputFormat::PsqlUnaligned);
+    }
+    if has_consistent_fields(lines, b',') {
+        return Some(InputFormat::Csv);
+    }
+
+    None
+}
+
+/// Check whether a line is a psql aligned separator (e.g., `----+-------`).
+fn is_psql_separator(line: &str) -> bool {
+    line.contains("---") && line.chars().all(|c| c == '-' || c == '+')
+}
+
+/// Check that every line splits into the same number (at least 2) of `|` fields.
+///
+/// psql unaligned output does not quote values, so a plain split is used.
+/// The row-count footer is ignored.
+fn has_consistent_pipes(lines: &[&str]) -> bool {
+    let mut counts = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty() && !parser::is_psql_footer(line))
+        .map(|line| line.split('|').count());
+
+    match counts.next() {
+        Some(header_count) if header_count >= 2 => counts.all(|c| c == header_count),
+        _ => false,
+    }
+}
+
+/// Check that the probe lines parse as delimited records with the same number
+/// (at least 2) of fields as the header.
+///
+/// The last record is allowed to differ because the probe may end in the
+/// middle of a quoted field that spans lines.
+fn has_consistent_fields(lines: &[&str], delimiter: u8) -> bool {
+    let text: String = lines
+        .iter()
+        .map(|line| parser::trim_line_ending(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut reader = delimited::delimited_reader(text.as_bytes(), delimiter);
+    let header_count = match reader.byte_headers() {
+        Ok(headers) if headers.len() >= 2 => headers.len(),
+        _ => return false,
+    };
+
+    let counts: Vec<usize> = reader
+        .byte_records()
+        .map_while(Result::ok)
+        .map(|record| record.len())
+        .collect();
+
+    match counts.split_last() {
+        Some((_, complete)) => complete.iter().all(|&c| c == header_count),
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(input: &str) -> Option<InputFormat> {
+        let lines: Vec<&str> = input.lines().collect();
+        detect_format(&lines)
+    }
+
+    #[test]
+    fn test_detect_psql_aligned() {
+        let input = " id | name\n----+------\n 1  | Alice\n(1 row)";
+        assert_eq!(detect(input), Some(InputFormat::Psql));
+    }
+
+    #[test]
+    fn test_detect_psql_single_column() {
+        assert_eq!(detect(" x\n---\n 1\n"), Some(InputFormat::Psql));
+    }
+
+    #[test]
+    fn test_detect_psql_expanded() {
+        let input = "-[ RECORD 1 ]-\nid   | 1\nname | Alice\n";
+        assert_eq!(detect(input), Some(InputFormat::PsqlExpanded));
+    }
+
+    #[test]
+    fn test_detect_psql_unaligned() {
+        let input = "id|name\n1|Smith, John\n2|Bob\n(2 rows)";
+        assert_eq!(detect(input), Some(InputFormat::PsqlUnaligned));
+    }
+
+    #[test]
+    fn test_detect_csv() {
+        let input = "id,name,url\n1,Alice,http://a|b\n2,\"Bob, Jr.\",x\n";
+        assert_eq!(detect(input), Some(InputFormat::Csv));
+    }
+
+    #[test]
+    fn test_detect_csv_with_multiline_field() {
+        let input = "id,note\n1,\"first\nsecond\"\n2,plain\n";
+        assert_eq!(detect(input), Some(InputFormat::Csv));
+    }
+
+    #[test]
+    fn test_detect_tsv() {
+        let input = "id\tname\n1\tAlice, Jr.\n";
+        assert_eq!(detect(input), Some(InputFormat::Tsv));
+    }
+
+    #[test]
+    fn test_detect_json() {
+        assert_eq!(detect("[\n  {\"a\": 1}\n]"), Some(InputFormat::Json));
+        assert_eq!(detect("{\n  \"a\": 1\n}"), Some(InputFormat::Json));
+    }
+
+    #[test]
+    fn test_detect_ndjson() {
+        let input = "{\"a\": 1}\n{\"a\": 2}\n";
+        assert_eq!(detect(input), Some(InputFormat::Ndjson));
+    }
+
+    #[test]
+    fn test_detect_markdown() {
+        let input = "| id | name |\n|----|------|\n| 1  | Alice |";
+        assert_eq!(detect(input), Some(InputFormat::Markdown));
+    }
+
+    #[test]
+    fn test_detect_unknown() {
+        assert_eq!(detect("just some text\nmore text\n"), None);
+        assert_eq!(detect(""), None);
+    }
+}
@@ -3,7 +3,7 @@
 //! Exports table data respecting column visibility and display order.
 
 use crate::parser::TableData;
-use std::collections::HashMap;
+use serde_json::{Map, Value};
 
 /// Export format selection
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -12,6 +12,13 @@ pub enum ExportFormat {
     Json,
 }
 
+/// Settings for exported values.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Text written for NULL cells in CSV (empty by default)
+    pub csv_null: String,
+}
+
 /// Export table data to a string in the specified format.
 ///
 /// Only exports visible columns in the order specified by `visible_cols`.
@@ -20,9 +27,21 @@ pub fn export_table(
     data: &TableData,
     visible_cols: &[usize],
     format: ExportFormat,
+) -> Result<String, String> {
+    export_table_with_options(data, visible_cols, format, &ExportOptions::default())
+}
+
+/// Export table data like `export_table`, using the given options.
+///
+/// NULL cells are written as `null` in JSON and as `options.csv_null` in CSV.
+pub fn export_table_with_options(
+    data: &TableData,
+    visible_cols: &[usize],
+    format: ExportFormat,
+    options: &ExportOptions,
 ) -> Result<String, String> {
     match format {
-        ExportFormat::Csv => export_csv(data, visible_cols),
+        ExportFormat::Csv => export_csv(data, visible_cols, &options.csv_null),
         ExportFormat::Json => export_json(data, visible_cols),
     }
 }
@@ -31,7 +50,7 @@ pub fn export_table(
 const UTF8_BOM: &str = "\u{FEFF}";
 
 /// Export to CSV format with UTF-8 BOM for Excel compatibility
-fn export_csv(data: &TableData, visible_cols: &[usize]) -> Result<String, String> {
+fn export_csv(data: &TableData, visible_cols: &[usize], null: &str) -> Result<String, String> {
     let mut wtr = csv::Writer::from_writer(Vec::new());
 
     // Write headers (only visible columns in order)
@@ -48,7 +67,7 @@ fn export_csv(data: &TableData, visible_cols: &[usize]) -> Result<String, String
             .iter()
             .map(|&i| {
                 row.get(i)
-                    .map(|s| data.resolve(s).to_string())
+                    .map(|s| data.value(s).unwrap_or(null).to_string())
                     .unwrap_or_default()
             })
             .collect();
@@ -68,19 +87,21 @@ fn export_csv(data: &TableData, visible_cols: &[usize]) -> Result<String, String
     Ok(format!("{}{}", UTF8_BOM, csv_content))
 }
 
-/// Export to JSON format (array of objects)
+/// Export to JSON format (array of objects, keys in column order)
 fn export_json(data: &TableData, visible_cols: &[usize]) -> Result<String, String> {
-    let mut rows_json: Vec<HashMap<&str, String>> = Vec::new();
+    let mut rows_json: Vec<Map<String, Value>> = Vec::new();
 
     for row in &data.rows {
-        let mut row_obj: HashMap<&str, String> = HashMap::new();
+        let mut row_obj = Map::new();
         for &col_idx in visible_cols {
             if let Some(header) = data.headers.get(col_idx) {
-                let value = row
-                    .get(col_idx)
-                    .map(|s| data.resolve(s).to_string())
-                    .unwrap_or_default();
-                row_obj.insert(header.as_str(), value);
+                let value = match row.get(col_idx) {
+                    Some(s) => data
+                        .value(s)
+                        .map_or(Value::Null, |v| Value::String(v.to_string())),
+                    None => Value::String(String::new()),
+                };
+                row_obj.insert(header.clone(), value);
             }
         }
         rows_json.push(row_obj);
@@ -97,6 +118,8 @@ pub fn save_to_file(content: &str, path: &str) -> Result<(), String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::null_cell;
+    use std::collections::HashMap;
 
     fn sample_table() -> TableData {
         use lasso::Rodeo;
@@ -113,11 +136,11 @@ mod tests {
                 interner.get_or_intern("25"),
             ],
         ];
-        TableData {
-            headers: vec!["id".to_string(), "name".to_string(), "age".to_string()],
+        TableData::new(
+            vec!["id".to_string(), "name".to_string(), "age".to_string()],
             rows,
             interner,
-        }
+        )
     }
 
     #[test]
@@ -183,11 +206,7 @@ mod tests {
     #[test]
     fn test_export_empty_table() {
         use lasso::Rodeo;
-        let data = TableData {
-            headers: vec!["col1".to_string()],
-            rows: vec![],
-            interner: Rodeo::default(),
-        };
+        let data = TableData::new(vec!["col1".to_string()], vec![], Rodeo::default());
         let visible = vec![0];
 
         // CSV should have just headers
@@ -198,4 +217,46 @@ mod tests {
         let json_result = export_table(&data, &visible, ExportFormat::Json).unwrap();
         assert_eq!(json_result.trim(), "[]");
     }
+
+    fn table_with_nulls() -> TableData {
+        use lasso::Rodeo;
+        let mut interner = Rodeo::default();
+        let rows = vec![vec![
+            interner.get_or_intern("1"),
+            null_cell(),
+            interner.get_or_intern(""),
+        ]];
+        TableData::new(
+            vec!["id".to_string(), "missing".to_string(), "empty".to_string()],
+            rows,
+            interner,
+        )
+    }
+
+    #[test]
+    fn test_export_null_json() {
+        let data = table_with_nulls();
+        let result = export_table(&data, &[0, 1, 2], ExportFormat::Json).unwrap();
+
+        let parsed: Vec<Value> = serde_json::from_str(&result).unwrap();
+        assert_eq!(
+            parsed[0],
+            serde_json::json!({"id": "1", "missing": null, "empty": ""})
+        );
+    }
+
+    #[test]
+    fn test_export_null_csv() {
+        let data = table_with_nulls();
+
+        let result = export_table(&data, &[0, 1, 2], ExportFormat::Csv).unwrap();
+        assert!(result.contains("1,,\n"));
+
+        let options = ExportOptions {
+            csv_null: "\\N".to_string(),
+        };
+        let result =
+            export_table_with_options(&data, &[0, 1, 2], ExportFormat::Csv, &options).unwrap();
+        assert!(result.contains("1,\\N,\n"));
+    }
 }
@@ -7,10 +7,11 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::widgets::TableState;
 
 use crate::column::ColumnConfig;
-use crate::db;
-use crate::export::{self, ExportFormat};
-use crate::parser::TableData;
+use crate::export::{self, ExportFormat, ExportOptions};
+use crate::history::QueryHistory;
+use crate::parser::{self, TableData};
 use crate::render::calculate_auto_widths;
+use crate::schema::{SchemaTree, TreeNode};
 use crate::state::AppMode;
 use crate::workspace::{Tab, ViewMode};
 
@@ -27,9 +28,13 @@ pub enum KeyAction {
     /// Create a new tab with the given data
     CreateTab {
         name: String,
-        data: TableData,
+        data: Box<TableData>,
         view_mode: ViewMode,
     },
+    /// Run a SQL query in the background
+    RunQuery(String),
+    /// Run a SQL query under EXPLAIN ANALYZE in the background and show its plan
+    ExplainQuery(String),
     /// Change input mode
     ModeChange(AppMode),
     /// Perform a workspace operation
@@ -52,6 +57,22 @@ pub enum WorkspaceOp {
     SwitchTo(usize),
     /// Close tab at the focused index
     CloseTab,
+    /// Open a table in a new tab, fetching rows through a cursor
+    OpenTable { schema: String, table: String },
+    /// Load a relation's columns into the focused tab's schema tree and expand it
+    LoadColumns(TreeNode),
+    /// Open a new tab describing a relation's columns, indexes and constraints
+    Describe { schema: String, table: String },
+    /// Open the rows a foreign key on the selected column leads to: the
+    /// referenced row, or with `reverse` the rows referencing this one
+    FollowForeignKey {
+        schema: String,
+        table: String,
+        column: String,
+        /// The selected row as (column, value) pairs, None for NULL
+        row: Vec<(String, Option<String>)>,
+        reverse: bool,
+    },
 }
 
 /// Handle key events in normal mode.
@@ -62,8 +83,8 @@ pub enum WorkspaceOp {
 pub fn handle_normal_mode(
     key: &KeyEvent,
     tab: &mut Tab,
-    db_client: &mut Option<postgres::Client>,
-    table_list_cache: &Option<TableData>,
+    db_connected: bool,
+    table_list_cache: &Option<SchemaTree>,
     current_table_name: &mut Option<String>,
     displayed_row_count: usize,
     has_split: bool,
@@ -74,65 +95,183 @@ pub fn handle_normal_mode(
         KeyCode::Char('q') => KeyAction::Quit,
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => KeyAction::Quit,
 
-        // Enter: Select table in TableList mode
+        // Enter: Expand/collapse the selected plan node
+        KeyCode::Enter if tab.plan.is_some() => {
+            if let Some(node) = selected_plan_node(tab) {
+                let expanded = tab.plan.as_ref().unwrap().is_expanded(node);
+                set_plan_node_expanded(tab, node, !expanded);
+            }
+            KeyAction::None
+        }
+
+        // Enter: Open the selected table, or expand/collapse a schema or group
         KeyCode::Enter => {
-            if tab.view_mode == ViewMode::TableList {
-                if let Some(ref mut client) = db_client {
-                    if let Some(selected) = tab.table_state.selected() {
-                        // Recalculate display_rows for event handling
-                        let filter_lower = tab.filter_text.to_lowercase();
-                        let display_rows: Vec<&Vec<lasso::Spur>> = if tab.filter_text.is_empty() {
-                            tab.data.rows.iter().collect()
-                        } else {
-                            tab.data
-                                .rows
-                                .iter()
-                                .filter(|row| {
-                                    row.iter().any(|cell| {
-                                        tab.data
-                                            .resolve(cell)
-                                            .to_lowercase()
-                                            .contains(&filter_lower)
-                                    })
-                                })
-                                .collect()
-                        };
-                        if let Some(row) = display_rows.get(selected) {
-                            if let Some(tbl_spur) = row.first() {
-                                let tbl_name = tab.data.resolve(tbl_spur).to_string();
-                                let query = format!("SELECT * FROM \"{}\" LIMIT 1000", tbl_name);
-                                match db::execute_query(client, &query) {
-                                    Ok(data) => {
-                                        if data.headers.is_empty() && data.rows.is_empty() {
-                                            return KeyAction::StatusMessage(
-                                                "Table is empty".to_string(),
-                                            );
-                                        } else {
-                                            *current_table_name = Some(tbl_name.clone());
-                                            return KeyAction::CreateTab {
-                                                name: tbl_name.clone(),
-                                                data,
-                                                view_mode: ViewMode::TableData,
-                                            };
-                                        }
-                                    }
-                                    Err(e) => {
-                                        return KeyAction::StatusMessage(format!("Error: {}", e));
-                                    }
-                                }
-                            }
-                        }
+            if tab.view_mode != ViewMode::TableList || !db_connected {
+                return KeyAction::None;
+            }
+            let Some(node) = selected_tree_node(tab) else {
+                return KeyAction::None;
+            };
+            let tree = tab.schema_tree.as_ref().unwrap();
+            match node {
+                TreeNode::Object(..) => {
+                    let (schema, object) = tree.object(node).unwrap();
+                    if object.kind.is_relation() {
+                        KeyAction::Workspace(WorkspaceOp::OpenTable {
+                            schema: schema.to_string(),
+                            table: object.name.clone(),
+                        })
+                    } else {
+                        KeyAction::StatusMessage("Functions have no rows to show".to_string())
                     }
                 }
+                TreeNode::Schema(_) | TreeNode::Group(..) => {
+                    let expanded = tree.is_expanded(node);
+                    set_tree_node_expanded(tab, node, !expanded);
+                    KeyAction::None
+                }
+                TreeNode::Column(..) => KeyAction::None,
+            }
+        }
+
+        // Right/l: Expand the selected tree node (loading a relation's columns)
+        KeyCode::Char('l') | KeyCode::Right if tab.schema_tree.is_some() => {
+            let Some(node) = selected_tree_node(tab) else {
+                return KeyAction::None;
+            };
+            let tree = tab.schema_tree.as_ref().unwrap();
+            if tree.needs_columns(node) {
+                return KeyAction::Workspace(WorkspaceOp::LoadColumns(node));
+            }
+            if tree.can_expand(node) && !tree.is_expanded(node) {
+                set_tree_node_expanded(tab, node, true);
+            }
+            KeyAction::None
+        }
+
+        // Right/l and Left/h: Expand or collapse the selected plan node, or go to its parent
+        KeyCode::Char('l') | KeyCode::Right if tab.plan.is_some() => {
+            if let Some(node) = selected_plan_node(tab) {
+                set_plan_node_expanded(tab, node, true);
+            }
+            KeyAction::None
+        }
+        KeyCode::Char('h') | KeyCode::Left if tab.plan.is_some() => {
+            let Some(node) = selected_plan_node(tab) else {
+                return KeyAction::None;
+            };
+            let plan = tab.plan.as_ref().unwrap();
+            if plan.is_expanded(node) {
+                set_plan_node_expanded(tab, node, false);
+            } else if let Some(line) = plan.nodes[node].parent.and_then(|p| plan.line_of(p)) {
+                // Rows only match plan lines without a filter
+                if tab.filter_text.is_empty() {
+                    tab.table_state.select(Some(line));
+                }
             }
             KeyAction::None
         }
 
+        // Left/h: Collapse the selected tree node, or go to its parent
+        KeyCode::Char('h') | KeyCode::Left if tab.schema_tree.is_some() => {
+            let Some(node) = selected_tree_node(tab) else {
+                return KeyAction::None;
+            };
+            let tree = tab.schema_tree.as_ref().unwrap();
+            if tree.is_expanded(node) {
+                set_tree_node_expanded(tab, node, false);
+            } else if let Some(line) = tree.parent(node).and_then(|p| tree.line_of(p)) {
+                // Rows only match tree lines without a filter
+                if tab.filter_text.is_empty() {
+                    tab.table_state.select(Some(line));
+                }
+            }
+            KeyAction::None
+        }
+
+        // Describe the selected table, or the one this tab shows (D key)
+        KeyCode::Char('D') if is_plain_key(key) => {
+            if !db_connected {
+                return KeyAction::StatusMessage("Describe requires --connect".to_string());
+            }
+            let relation = match tab.view_mode {
+                ViewMode::TableList => selected_tree_node(tab).and_then(|node| {
+                    let (schema, object) = tab.schema_tree.as_ref()?.object(node)?;
+                    object
+                        .kind
+                        .is_relation()
+                        .then(|| (schema.to_string(), object.name.clone()))
+                }),
+                ViewMode::TableData => tab.relation.clone(),
+                ViewMode::PipeData => None,
+            };
+            match relation {
+                Some((schema, table)) => {
+                    KeyAction::Workspace(WorkspaceOp::Describe { schema, table })
+                }
+                None => KeyAction::StatusMessage("No table to describe".to_string()),
+            }
+        }
+
+        // Show the plan of the query behind this tab (X key)
+        KeyCode::Char('X') if is_plain_key(key) => {
+            if !db_connected {
+                return KeyAction::StatusMessage("EXPLAIN requires --connect".to_string());
+            }
+            match tab.query {
+                Some(ref query) => KeyAction::ExplainQuery(query.clone()),
+                None => KeyAction::StatusMessage("No query to explain in this tab".to_string()),
+            }
+        }
+
+        // Follow a foreign key to the referenced row (f), or list the rows
+        // referencing the selected one (r)
+        KeyCode::Char(c @ ('f' | 'r'))
+            if tab.view_mode == ViewMode::TableData && is_plain_key(key) =>
+        {
+            let Some((schema, table)) = tab.relation.clone() else {
+                return KeyAction::StatusMessage(
+                    "Foreign keys can be followed in tables opened from the schema browser"
+                        .to_string(),
+                );
+            };
+            let Some(row) = tab
+                .table_state
+                .selected()
+                .and_then(|selected| tab.displayed_row_index(selected))
+            else {
+                return KeyAction::None;
+            };
+            let visible = tab.column_config.visible_indices();
+            let Some(&col) = visible.get(tab.selected_visible_col) else {
+                return KeyAction::None;
+            };
+            let data = &tab.data;
+            let row = data
+                .headers
+                .iter()
+                .zip(&data.rows[row])
+                .map(|(header, spur)| (header.clone(), data.value(spur).map(str::to_string)))
+                .collect();
+            KeyAction::Workspace(WorkspaceOp::FollowForeignKey {
+                schema,
+                table,
+                column: data.headers[col].clone(),
+                row,
+                reverse: c == 'r',
+            })
+        }
+
         // Esc: Go back to table list from TableData mode
         KeyCode::Esc => {
             if tab.view_mode == ViewMode::TableData {
                 if let Some(ref cached) = table_list_cache {
-                    tab.data = cached.clone();
+                    tab.schema_tree = Some(cached.clone());
+                    tab.relation = None;
+                    tab.query = None;
+                    tab.plan = None;
+                    tab.highlighted_rows.clear();
+                    tab.refresh_schema_tree();
                     tab.column_config = ColumnConfig::new(tab.data.headers.len());
                     tab.scroll_col_offset = 0;
                     tab.selected_visible_col = 0;
@@ -147,7 +286,7 @@ pub fn handle_normal_mode(
 
         // Enter query input mode (only in DB modes, not pipe)
         KeyCode::Char(':') => {
-            if db_client.is_some() {
+            if db_connected {
                 KeyAction::ModeChange(AppMode::QueryInput)
             } else {
                 KeyAction::StatusMessage("Query mode requires --connect".to_string())
@@ -329,6 +468,30 @@ pub fn handle_normal_mode(
             KeyAction::None
         }
 
+        // Toggle auto-scroll to the newest row while rows stream in (F key)
+        KeyCode::Char('F') => {
+            tab.auto_scroll = !tab.auto_scroll;
+            if tab.auto_scroll {
+                tab.select_last_row();
+                KeyAction::StatusMessage("Auto-scroll on".to_string())
+            } else {
+                KeyAction::StatusMessage("Auto-scroll off".to_string())
+            }
+        }
+
+        // Open malformed input lines in a new tab (I key)
+        KeyCode::Char('I') => {
+            if tab.issues.is_empty() {
+                KeyAction::StatusMessage("No malformed lines".to_string())
+            } else {
+                KeyAction::CreateTab {
+                    name: format!("{} issues", tab.name),
+                    data: Box::new(parser::issues_table(&tab.issues)),
+                    view_mode: ViewMode::PipeData,
+                }
+            }
+        }
+
         // Export data (E key)
         KeyCode::Char('E') => {
             // Export available in TableData and PipeData modes
@@ -393,85 +556,138 @@ pub fn handle_normal_mode(
     }
 }
 
+/// Tab name for a query result (the query, truncated if long).
+pub fn query_tab_name(query: &str) -> String {
+    let query = query.trim();
+    if query.chars().count() > 20 {
+        format!("{}...", query.chars().take(17).collect::<String>())
+    } else {
+        query.to_string()
+    }
+}
+
+/// The schema tree node on the selected row, if the tab shows a tree.
+fn selected_tree_node(tab: &Tab) -> Option<TreeNode> {
+    let row = tab.displayed_row_index(tab.table_state.selected()?)?;
+    tab.schema_tree.as_ref()?.node(row)
+}
+
+/// Expand or collapse a schema tree node and show the updated tree.
+pub fn set_tree_node_expanded(tab: &mut Tab, node: TreeNode, expanded: bool) {
+    if let Some(tree) = tab.schema_tree.as_mut() {
+        tree.set_expanded(node, expanded);
+    }
+    tab.refresh_schema_tree();
+}
+
+/// Whether a key was pressed without modifiers (other than Shift), so that
+/// Ctrl/Alt combinations don't trigger letter commands that open connections.
+fn is_plain_key(key: &KeyEvent) -> bool {
+    key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT
+}
+
+/// The plan node on the selected row, if the tab shows a plan.
+fn selected_plan_node(tab: &Tab) -> Option<usize> {
+    let row = tab.displayed_row_index(tab.table_state.selected()?)?;
+    tab.plan.as_ref()?.node(row)
+}
+
+/// Expand or collapse a plan node and show the updated plan.
+fn set_plan_node_expanded(tab: &mut Tab, node: usize, expanded: bool) {
+    if let Some(plan) = tab.plan.as_mut() {
+        plan.set_expanded(node, expanded);
+    }
+    tab.refresh_plan();
+}
+
 /// Handle key events in query input mode.
 ///
 /// Returns (KeyAction, bool) where bool indicates whether to return to Normal mode.
+/// The query itself runs in the background (see `KeyAction::RunQuery`).
+/// Up/Down recall previous queries and Ctrl+R searches them.
 pub fn handle_query_input(
     key: &KeyEvent,
     input_buffer: &mut String,
-    db_client: &mut Option<postgres::Client>,
+    history: &mut QueryHistory,
+    db_connected: bool,
 ) -> (KeyAction, bool) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    if history.is_searching() {
+        match key.code {
+            // Ctrl+R again: older match
+            KeyCode::Char('r') if ctrl => history.search_older(),
+            // Ctrl+G: cancel the search, restoring what was typed
+            KeyCode::Char('g') if ctrl => *input_buffer = history.end_search(false),
+            // Esc: keep the match in the prompt to edit it
+            KeyCode::Esc => *input_buffer = history.end_search(true),
+            // Enter: run the match (below)
+            KeyCode::Enter => *input_buffer = history.end_search(true),
+            KeyCode::Char(c) if !ctrl => history.search_push(c),
+            KeyCode::Backspace => history.search_pop(),
+            _ => {}
+        }
+        if key.code != KeyCode::Enter {
+            return (KeyAction::None, false);
+        }
+    }
+
     match key.code {
         // Cancel and return to normal mode
         KeyCode::Esc => {
             input_buffer.clear();
+            history.reset();
             (KeyAction::None, true)
         }
 
         // Execute query and return to normal mode
         KeyCode::Enter => {
-            if let Some(ref mut client) = db_client {
-                // Execute query via database client
-                let query_str = input_buffer.trim().to_string();
-                if !query_str.is_empty() {
-                    match db::execute_query(client, &query_str) {
-                        Ok(data) => {
-                            if data.headers.is_empty() && data.rows.is_empty() {
-                                input_buffer.clear();
-                                return (
-                                    KeyAction::StatusMessage(
-                                        "Query returned no results".to_string(),
-                                    ),
-                                    true,
-                                );
-                            } else {
-                                // Generate tab name from query (truncate if long)
-                                let tab_name = {
-                                    let q = query_str.trim();
-                                    if q.len() > 20 {
-                                        format!("{}...", &q[..17])
-                                    } else {
-                                        q.to_string()
-                                    }
-                                };
-
-                                input_buffer.clear();
-                                return (
-                                    KeyAction::CreateTab {
-                                        name: tab_name,
-                                        data,
-                                        view_mode: ViewMode::TableData,
-                                    },
-                                    true,
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            input_buffer.clear();
-                            return (KeyAction::StatusMessage(format!("Error: {}", e)), true);
-                        }
-                    }
-                }
-            } else {
+            let query_str = input_buffer.trim().to_string();
+            input_buffer.clear();
+            history.reset();
+            if !db_connected {
                 // Not in database mode
-                input_buffer.clear();
                 return (
                     KeyAction::StatusMessage("Query mode requires --connect".to_string()),
                     true,
                 );
             }
-            input_buffer.clear();
-            (KeyAction::None, true)
+            if query_str.is_empty() {
+                (KeyAction::None, true)
+            } else {
+                (KeyAction::RunQuery(query_str), true)
+            }
         }
 
-        // Text input
-        KeyCode::Char(c) => {
+        // Recall previous and next queries
+        KeyCode::Up => {
+            if let Some(query) = history.older(input_buffer) {
+                *input_buffer = query;
+            }
+            (KeyAction::None, false)
+        }
+        KeyCode::Down => {
+            if let Some(query) = history.newer() {
+                *input_buffer = query;
+            }
+            (KeyAction::None, false)
+        }
+
+        // Ctrl+R: search previous queries
+        KeyCode::Char('r') if ctrl => {
+            history.start_search(input_buffer);
+            (KeyAction::None, false)
+        }
+
+        // Text input (editing a recalled query stops browsing)
+        KeyCode::Char(c) if !ctrl => {
+            history.reset();
             input_buffer.push(c);
             (KeyAction::None, false)
         }
 
         // Backspace
         KeyCode::Backspace => {
+            history.reset();
             input_buffer.pop();
             (KeyAction::None, false)
         }
@@ -559,6 +775,7 @@ pub fn handle_export_filename(
     key: &KeyEvent,
     input_buffer: &mut String,
     export_format: Option<ExportFormat>,
+    export_options: &ExportOptions,
     tab: &Tab,
 ) -> (Option<String>, bool) {
     match key.code {
@@ -574,7 +791,12 @@ pub fn handle_export_filename(
             if !filename.is_empty() {
                 if let Some(fmt) = export_format {
                     let visible_cols = tab.column_config.visible_indices();
-                    match export::export_table(&tab.data, &visible_cols, fmt) {
+                    match export::export_table_with_options(
+                        &tab.data,
+                        &visible_cols,
+                        fmt,
+                        export_options,
+                    ) {
                         Ok(content) => match export::save_to_file(&content, &filename) {
                             Ok(()) => {
                                 input_buffer.clear();
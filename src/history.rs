@@ -0,0 +1,489 @@
+//! Query history: queries run from the `:` prompt, kept per connection.
+//!
+//! Each connection (user, host, port and database) has its own file under
+//! `$XDG_STATE_HOME/pte/history/`, one JSON object per line. Entries are
+//! appended as queries finish, so several sessions can share a history.
+
+use crate::conninfo::{home_dir, ConnParams};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Entries kept when loading a history file; older ones are dropped.
+pub const MAX_ENTRIES: usize = 1000;
+
+/// A query that was run, with how long it took and what it returned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub query: String,
+    /// Unix time (seconds) at which the query finished
+    pub finished_at: u64,
+    pub duration_ms: u64,
+    /// Rows returned, or None when the statement had no result set
+    pub rows: Option<usize>,
+    #[serde(default)]
+    pub failed: bool,
+}
+
+impl HistoryEntry {
+    /// Outcome of the query, e.g. "12 rows in 35 ms".
+    pub fn summary(&self) -> String {
+        let outcome = match self.rows {
+            _ if self.failed => "failed".to_string(),
+            Some(1) => "1 row".to_string(),
+            Some(n) => format!("{} rows", n),
+            None => "no result set".to_string(),
+        };
+        format!("{} in {}", outcome, format_duration(self.duration_ms))
+    }
+}
+
+/// Format milliseconds as "35 ms", "2.5 s" or "3m 12s".
+fn format_duration(ms: u64) -> String {
+    match Duration::from_millis(ms).as_secs() {
+        _ if ms < 1000 => format!("{} ms", ms),
+        secs if secs < 60 => format!("{:.1} s", ms as f64 / 1000.0),
+        secs => format!("{}m {}s", secs / 60, secs % 60),
+    }
+}
+
+/// An in-progress Ctrl+R search.
+#[derive(Debug, Default)]
+struct Search {
+    term: String,
+    /// Index of the entry matching `term`
+    matched: Option<usize>,
+}
+
+/// Previous queries, with Up/Down browsing and reverse search.
+#[derive(Debug, Default)]
+pub struct QueryHistory {
+    /// File new entries are appended to; None keeps history in memory
+    path: Option<PathBuf>,
+    entries: Vec<HistoryEntry>,
+    /// Entry shown while browsing with Up/Down
+    position: Option<usize>,
+    /// Input typed before browsing or searching, restored when leaving
+    draft: String,
+    search: Option<Search>,
+}
+
+impl QueryHistory {
+    /// Load the history file at `path`; a missing file is an empty history.
+    ///
+    /// Lines that cannot be parsed are skipped, and only the newest entry of a
+    /// repeated query is kept (as `record` does). The file is rewritten without
+    /// the oldest entries once it grows well past `MAX_ENTRIES`.
+    pub fn open(path: PathBuf) -> Result<Self, String> {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("cannot read {}: {}", path.display(), e)),
+        };
+        let mut seen = HashSet::new();
+        let mut entries: Vec<HistoryEntry> = content
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+            .filter(|entry| seen.insert(entry.query.clone()))
+            .collect();
+        entries.reverse();
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+        if content.lines().count() > 2 * MAX_ENTRIES {
+            write_entries(&path, &entries)?;
+        }
+        Ok(QueryHistory {
+            path: Some(path),
+            entries,
+            ..Default::default()
+        })
+    }
+
+    /// All entries, oldest first.
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Add a finished query and append it to the history file.
+    ///
+    /// Running the same query again replaces its previous entry, so Up
+    /// steps through distinct queries.
+    pub fn record(&mut self, entry: HistoryEntry) -> Result<(), String> {
+        self.reset();
+        let line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+        self.entries.retain(|e| e.query != entry.query);
+        self.entries.push(entry);
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        append_line(path, &line).map_err(|e| format!("cannot write {}: {}", path.display(), e))
+    }
+
+    /// Stop browsing and searching.
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.search = None;
+        self.draft.clear();
+    }
+
+    /// The entry being browsed, or the current search match.
+    pub fn current(&self) -> Option<&HistoryEntry> {
+        match &self.search {
+            Some(search) => search.matched.map(|i| &self.entries[i]),
+            None => self.position.map(|i| &self.entries[i]),
+        }
+    }
+
+    /// Step to the next older query, remembering `input` when starting
+    /// to browse. Returns the text to show in the prompt.
+    pub fn older(&mut self, input: &str) -> Option<String> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = input.to_string();
+                self.entries.len() - 1
+            }
+            Some(position) => position.saturating_sub(1),
+        };
+        self.position = Some(position);
+        Some(self.entries[position].query.clone())
+    }
+
+    /// Step to the next newer query, back to what was typed after the
+    /// newest one. Returns the text to show in the prompt.
+    pub fn newer(&mut self) -> Option<String> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            Some(self.entries[position + 1].query.clone())
+        } else {
+            self.position = None;
+            Some(std::mem::take(&mut self.draft))
+        }
+    }
+
+    /// Start a reverse search, remembering `input` to restore on cancel.
+    pub fn start_search(&mut self, input: &str) {
+        self.position = None;
+        self.draft = input.to_string();
+        self.search = Some(Search::default());
+    }
+
+    /// Whether a reverse search is in progress.
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Text being searched for.
+    pub fn search_term(&self) -> Option<&str> {
+        self.search.as_ref().map(|search| search.term.as_str())
+    }
+
+    /// Add a character to the search term, keeping the current match if it
+    /// still matches.
+    pub fn search_push(&mut self, c: char) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        search.term.push(c);
+        let before = search.matched.map_or(self.entries.len(), |i| i + 1);
+        self.search_before(before);
+    }
+
+    /// Remove the last character of the search term and search again.
+    pub fn search_pop(&mut self) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        search.term.pop();
+        self.search_before(self.entries.len());
+    }
+
+    /// Move to the next older match (Ctrl+R again). Keeps the current match
+    /// when there is none.
+    pub fn search_older(&mut self) {
+        let Some(matched) = self.search.as_ref().and_then(|search| search.matched) else {
+            return;
+        };
+        if let Some(older) = self.find(matched) {
+            if let Some(search) = self.search.as_mut() {
+                search.matched = Some(older);
+            }
+        }
+    }
+
+    /// Finish searching. Returns the matched query when `accept` is set (or
+    /// what was typed before searching when nothing matched), and what was
+    /// typed before searching otherwise.
+    pub fn end_search(&mut self, accept: bool) -> String {
+        let matched = self
+            .search
+            .take()
+            .and_then(|search| search.matched)
+            .filter(|_| accept);
+        let draft = std::mem::take(&mut self.draft);
+        match matched {
+            Some(i) => self.entries[i].query.clone(),
+            None => draft,
+        }
+    }
+
+    fn search_before(&mut self, before: usize) {
+        let matched = self.find(before);
+        if let Some(search) = self.search.as_mut() {
+            search.matched = matched;
+        }
+    }
+
+    /// Newest entry before index `before` containing the search term,
+    /// ignoring case.
+    fn find(&self, before: usize) -> Option<usize> {
+        let term = self.search.as_ref()?.term.to_lowercase();
+        if term.is_empty() {
+            return None;
+        }
+        self.entries[..before]
+            .iter()
+            .rposition(|entry| entry.query.to_lowercase().contains(&term))
+    }
+}
+
+/// Options for writing a history file. Queries can contain passwords
+/// (`ALTER ROLE ... PASSWORD`), so new files are readable by their owner only.
+fn private_file_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+}
+
+fn append_line(path: &Path, line: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = private_file_options().append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+fn write_entries(path: &Path, entries: &[HistoryEntry]) -> Result<(), String> {
+    let mut content = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+        content.push_str(&line);
+        content.push('\n');
+    }
+    private_file_options()
+        .write(true)
+        .truncate(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| format!("cannot write {}: {}", path.display(), e))
+}
+
+/// Path of the history file for a connection:
+/// `$XDG_STATE_HOME/pte/history/<user>@<host>_<port>_<dbname>.jsonl`, by
+/// default under `~/.local/state` (`%LOCALAPPDATA%` on Windows).
+///
+/// Passwords and other parameters are left out of the name, so the same
+/// database shares one history however it is reached. Without a `user`, the
+/// server's default (the login name) is used, as for `.pgpass`.
+pub fn history_path(
+    connection_string: &str,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Option<PathBuf> {
+    let params = ConnParams::parse(connection_string).ok()?;
+    let user = params
+        .get("user")
+        .map(str::to_string)
+        .or_else(|| env("USER"))
+        .or_else(|| env("USERNAME"))
+        .unwrap_or_default();
+    let host = params.get("host").or(params.get("hostaddr")).unwrap_or("");
+    let port = params.get("port").unwrap_or("5432");
+    let dbname = params.get("dbname").unwrap_or(&user);
+    let name = match user.as_str() {
+        "" => format!("{}_{}_{}", host, port, dbname),
+        user => format!("{}@{}_{}_{}", user, host, port, dbname),
+    };
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "@._-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let dir = env("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            if cfg!(windows) {
+                env("LOCALAPPDATA").map(PathBuf::from)
+            } else {
+                home_dir(env).map(|home| home.join(".local").join("state"))
+            }
+        })?;
+    Some(
+        dir.join("pte")
+            .join("history")
+            .join(format!("{}.jsonl", name)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(query: &str) -> HistoryEntry {
+        HistoryEntry {
+            query: query.to_string(),
+            finished_at: 0,
+            duration_ms: 12,
+            rows: Some(3),
+            failed: false,
+        }
+    }
+
+    fn history(queries: &[&str]) -> QueryHistory {
+        let mut history = QueryHistory::default();
+        for query in queries {
+            history.record(entry(query)).unwrap();
+        }
+        history
+    }
+
+    #[test]
+    fn test_browse_older_and_newer() {
+        let mut history = history(&["select 1", "select 2", "select 3"]);
+
+        assert_eq!(history.older("sel").as_deref(), Some("select 3"));
+        assert_eq!(history.older("").as_deref(), Some("select 2"));
+        assert_eq!(history.older("").as_deref(), Some("select 1"));
+        // Stays on the oldest entry
+        assert_eq!(history.older("").as_deref(), Some("select 1"));
+        assert_eq!(history.newer().as_deref(), Some("select 2"));
+        assert_eq!(history.newer().as_deref(), Some("select 3"));
+        // Back to what was typed
+        assert_eq!(history.newer().as_deref(), Some("sel"));
+        assert_eq!(history.newer(), None);
+
+        assert_eq!(QueryHistory::default().older("x"), None);
+    }
+
+    #[test]
+    fn test_repeated_query_moves_to_newest() {
+        let history = history(&["select 1", "select 2", "select 1"]);
+        let queries: Vec<&str> = history.entries().iter().map(|e| e.query.as_str()).collect();
+        assert_eq!(queries, vec!["select 2", "select 1"]);
+    }
+
+    #[test]
+    fn test_reverse_search() {
+        let mut history = history(&["SELECT * FROM orders", "select 2", "select id from orders"]);
+        history.start_search("typed");
+
+        history.search_push('o');
+        history.search_push('r');
+        assert_eq!(history.current().unwrap().query, "select id from orders");
+        // Ctrl+R again finds older matches, ignoring case
+        history.search_older();
+        assert_eq!(history.current().unwrap().query, "SELECT * FROM orders");
+        history.search_older();
+        assert_eq!(history.current().unwrap().query, "SELECT * FROM orders");
+
+        history.search_push('x');
+        assert_eq!(history.current(), None);
+        history.search_pop();
+        assert_eq!(history.current().unwrap().query, "select id from orders");
+
+        assert_eq!(history.end_search(true), "select id from orders");
+        assert!(!history.is_searching());
+
+        history.start_search("typed");
+        history.search_push('2');
+        assert_eq!(history.end_search(false), "typed");
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let dir = std::env::temp_dir().join(format!("pte-history-{}", std::process::id()));
+        let path = dir.join("history.jsonl");
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut history = QueryHistory::open(path.clone()).unwrap();
+        assert!(history.entries().is_empty());
+        history.record(entry("select 1")).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let mut failed = entry("selec 2");
+        failed.rows = None;
+        failed.failed = true;
+        history.record(failed.clone()).unwrap();
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "not json\n").unwrap();
+
+        let reopened = QueryHistory::open(path.clone()).unwrap();
+        assert_eq!(reopened.entries(), &[entry("select 1"), failed.clone()]);
+        assert_eq!(reopened.entries()[0].summary(), "3 rows in 12 ms");
+        assert_eq!(reopened.entries()[1].summary(), "failed in 12 ms");
+
+        // A query run again is listed once, as its newest run
+        let mut history = reopened;
+        let mut again = entry("select 1");
+        again.duration_ms = 99;
+        history.record(again.clone()).unwrap();
+        let reopened = QueryHistory::open(path).unwrap();
+        assert_eq!(reopened.entries(), &[failed, again]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_history_path_per_connection() {
+        let env = |key: &str| match key {
+            "HOME" => Some("/home/me".to_string()),
+            _ => None,
+        };
+        let path = history_path(
+            "host=db.example.com port=6432 user=app dbname=shop password=secret",
+            &env,
+        )
+        .unwrap();
+        assert_eq!(
+            path,
+            PathBuf::from("/home/me/.local/state/pte/history/app@db.example.com_6432_shop.jsonl")
+        );
+
+        let path = history_path("host=/tmp user=postgres", &env).unwrap();
+        assert!(path.ends_with("postgres@_tmp_5432_postgres.jsonl"));
+
+        // The login name is the default user
+        let login = |key: &str| match key {
+            "USER" => Some("me".to_string()),
+            _ => env(key),
+        };
+        let path = history_path("host=db port=5432", &login).unwrap();
+        assert!(path.ends_with("me@db_5432_me.jsonl"));
+        let path = history_path("host=db port=5432 dbname=shop", &env).unwrap();
+        assert!(path.ends_with("db_5432_shop.jsonl"));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(35), "35 ms");
+        assert_eq!(format_duration(2500), "2.5 s");
+        assert_eq!(format_duration(192_000), "3m 12s");
+    }
+}
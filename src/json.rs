@@ -0,0 +1,350 @@
+//! JSON and NDJSON input parsing.
+//!
+//! Turns a JSON array of objects, or newline-delimited JSON objects, into
+//! tabular rows. Headers are the union of keys in first-seen order, and
+//! nested objects are flattened into dotted column names (`user.address.city`).
+
+use crate::parser::{intern_cell, TableData};
+use lasso::{Rodeo, Spur};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Column name used for top-level values that are not objects
+const SCALAR_COLUMN: &str = "value";
+
+/// Splits a JSON text stream into complete top-level values, line by line.
+///
+/// If the input starts with `[`, each element of that array is emitted.
+/// Otherwise every top-level value is emitted, which covers NDJSON as well
+/// as pretty-printed objects concatenated one after another.
+#[derive(Debug, Default)]
+pub struct JsonValueSplitter {
+    /// Text of the value currently being collected
+    current: String,
+    /// Nesting depth of `{`/`[` within the current value
+    depth: usize,
+    /// Inside a string literal
+    in_string: bool,
+    /// Previous character was a backslash inside a string
+    escaped: bool,
+    /// Whether the start of the input has been seen (decides array mode)
+    started: bool,
+    /// Input is a top-level array whose elements are emitted
+    in_array: bool,
+}
+
+impl JsonValueSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one line of input, returning the text of each value it completes.
+    pub fn push_line(&mut self, line: &str) -> Vec<String> {
+        let mut values = Vec::new();
+
+        for c in line.chars() {
+            if !self.started {
+                if c.is_whitespace() {
+                    continue;
+                }
+                self.started = true;
+                if c == '[' {
+                    self.in_array = true;
+                    continue;
+                }
+            }
+
+            if self.in_string {
+                self.current.push(c);
+                if self.escaped {
+                    self.escaped = false;
+                } else if c == '\\' {
+                    self.escaped = true;
+                } else if c == '"' {
+                    self.in_string = false;
+                }
+                continue;
+            }
+
+            match c {
+                '"' => {
+                    self.in_string = true;
+                    self.current.push(c);
+                }
+                '{' | '[' => {
+                    self.depth += 1;
+                    self.current.push(c);
+                }
+                '}' | ']' if self.depth > 0 => {
+                    self.depth -= 1;
+                    self.current.push(c);
+                    if self.depth == 0 && !self.in_array {
+                        self.emit(&mut values);
+                    }
+                }
+                // Element separator or end of the top-level array
+                ',' | ']' if self.depth == 0 && self.in_array => self.emit(&mut values),
+                _ => self.current.push(c),
+            }
+        }
+
+        // A line boundary ends a bare scalar value outside of an array (NDJSON)
+        if self.depth == 0 && !self.in_array && !self.in_string {
+            self.emit(&mut values);
+        } else {
+            self.current.push('\n');
+        }
+
+        values
+    }
+
+    /// Flush whatever is left at end of input.
+    pub fn finish(&mut self) -> Option<String> {
+        let mut values = Vec::new();
+        self.emit(&mut values);
+        values.pop()
+    }
+
+    fn emit(&mut self, values: &mut Vec<String>) {
+        let text = std::mem::take(&mut self.current);
+        let trimmed = text.trim();
+        if !trimmed.is_empty() {
+            values.push(trimmed.to_string());
+        }
+    }
+}
+
+/// Builds rows from JSON values while tracking the union of column names.
+#[derive(Debug, Default)]
+pub struct JsonRowBuilder {
+    /// Column names in first-seen order
+    headers: Vec<String>,
+    /// Column name to index in `headers`
+    positions: HashMap<String, usize>,
+}
+
+impl JsonRowBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder whose columns start with `headers` (in that order).
+    pub fn with_headers(headers: &[String]) -> Self {
+        let mut builder = Self::new();
+        for header in headers {
+            builder.column_index(header);
+        }
+        builder
+    }
+
+    /// Column names seen so far.
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// Parse a JSON value's text into a row.
+    ///
+    /// Returns `None` if the text is not valid JSON.
+    pub fn row_from_text(&mut self, text: &str) -> Option<Vec<Option<String>>> {
+        let value: Value = serde_json::from_str(text).ok()?;
+        Some(self.row_from_value(&value))
+    }
+
+    /// Flatten a JSON value into a row, adding new columns as keys appear.
+    ///
+    /// Cells are placed by column index; columns the value does not have are
+    /// empty, and `null` values are `None`.
+    pub fn row_from_value(&mut self, value: &Value) -> Vec<Option<String>> {
+        let mut fields = Vec::new();
+        match value {
+            Value::Object(_) => flatten_value(value, "", &mut fields),
+            other => fields.push((SCALAR_COLUMN.to_string(), cell_text(other))),
+        }
+
+        let mut row = Vec::new();
+        for (key, cell) in fields {
+            let idx = self.column_index(&key);
+            if row.len() <= idx {
+                row.resize(idx + 1, Some(String::new()));
+            }
+            row[idx] = cell;
+        }
+        row
+    }
+
+    fn column_index(&mut self, key: &str) -> usize {
+        if let Some(&idx) = self.positions.get(key) {
+            return idx;
+        }
+        let idx = self.headers.len();
+        self.headers.push(key.to_string());
+        self.positions.insert(key.to_string(), idx);
+        idx
+    }
+}
+
+/// Flatten nested objects into `(dotted.key, cell)` pairs.
+/// Arrays are kept as compact JSON text rather than expanded into columns.
+fn flatten_value(value: &Value, prefix: &str, out: &mut Vec<(String, Option<String>)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() || prefix.is_empty() => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_value(child, &path, out);
+            }
+        }
+        other => out.push((prefix.to_string(), cell_text(other))),
+    }
+}
+
+/// Text shown in a cell for a JSON leaf value (`None` for `null`).
+fn cell_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Parse a JSON array of objects or NDJSON text into structured TableData.
+///
+/// Accepted formats:
+/// ```text
+/// [{"id": 1, "user": {"name": "Alice"}}, {"id": 2, "tags": ["a"]}]
+///
+/// {"id": 1, "user": {"name": "Alice"}}
+/// {"id": 2, "tags": ["a"]}
+/// ```
+///
+/// Values that are not valid JSON are skipped.
+/// Returns `None` if input contains no JSON values.
+pub fn parse_json(input: &str) -> Option<TableData> {
+    let mut splitter = JsonValueSplitter::new();
+    let mut builder = JsonRowBuilder::new();
+    let mut interner = Rodeo::default();
+    let mut rows: Vec<Vec<Spur>> = Vec::new();
+
+    let values = input
+        .lines()
+        .flat_map(|line| splitter.push_line(line))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .chain(splitter.finish());
+
+    for text in values {
+        if let Some(row) = builder.row_from_text(&text) {
+            rows.push(
+                row.iter()
+                    .map(|cell| intern_cell(&mut interner, cell.as_deref()))
+                    .collect(),
+            );
+        }
+    }
+
+    if rows.is_empty() {
+        return None;
+    }
+
+    Some(TableData::new(builder.headers, rows, interner))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve_rows(table: &TableData) -> Vec<Vec<String>> {
+        table
+            .rows
+            .iter()
+            .map(|row| table.resolve_row(row))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_json_array() {
+        let input = r#"[{"id": 1, "name": "Alice"}, {"id": 2, "name": "Bob"}]"#;
+
+        let table = parse_json(input).unwrap();
+        assert_eq!(table.headers, vec!["id", "name"]);
+        assert_eq!(
+            resolve_rows(&table),
+            vec![vec!["1", "Alice"], vec!["2", "Bob"]]
+        );
+    }
+
+    #[test]
+    fn test_parse_pretty_json_array() {
+        let input =
+            "[\n  {\n    \"id\": 1,\n    \"note\": \"a, [b]\"\n  },\n  {\n    \"id\": 2\n  }\n]\n";
+
+        let table = parse_json(input).unwrap();
+        assert_eq!(table.headers, vec!["id", "note"]);
+        assert_eq!(resolve_rows(&table), vec![vec!["1", "a, [b]"], vec!["2"]]);
+    }
+
+    #[test]
+    fn test_parse_ndjson_union_of_keys() {
+        let input = "{\"a\": 1}\n{\"b\": true}\n{\"a\": 3, \"c\": null}\n";
+
+        let table = parse_json(input).unwrap();
+        assert_eq!(table.headers, vec!["a", "b", "c"]);
+        assert_eq!(
+            resolve_rows(&table),
+            vec![vec!["1"], vec!["", "true"], vec!["3", "", ""]]
+        );
+        assert!(table.is_null(&table.rows[2][2]));
+        assert!(!table.is_null(&table.rows[2][1]));
+    }
+
+    #[test]
+    fn test_flatten_nested_objects() {
+        let input = r#"{"id": 1, "user": {"name": "Alice", "address": {"city": "Paris"}}, "tags": ["x", "y"], "meta": {}}"#;
+
+        let table = parse_json(input).unwrap();
+        assert_eq!(
+            table.headers,
+            vec!["id", "user.name", "user.address.city", "tags", "meta"]
+        );
+        assert_eq!(
+            resolve_rows(&table)[0],
+            vec!["1", "Alice", "Paris", "[\"x\",\"y\"]", "{}"]
+        );
+    }
+
+    #[test]
+    fn test_nul_string_is_not_null() {
+        let table = parse_json("[{\"a\": \"\\u0000\"}, {\"a\": null}]").unwrap();
+        assert_eq!(table.value(&table.rows[0][0]), Some("\0"));
+        assert!(table.is_null(&table.rows[1][0]));
+    }
+
+    #[test]
+    fn test_scalar_values() {
+        let table = parse_json("[1, \"two\"]").unwrap();
+        assert_eq!(table.headers, vec!["value"]);
+        assert_eq!(resolve_rows(&table), vec![vec!["1"], vec!["two"]]);
+    }
+
+    #[test]
+    fn test_invalid_lines_skipped() {
+        let table = parse_json("{\"a\": 1}\nnot json\n{\"a\": 2}\n").unwrap();
+        assert_eq!(table.row_count(), 2);
+    }
+
+    #[test]
+    fn test_splitter_escaped_quotes() {
+        let mut splitter = JsonValueSplitter::new();
+        let values = splitter.push_line(r#"[{"a": "say \"}\""}, {"a": "x"}]"#);
+        assert_eq!(values, vec![r#"{"a": "say \"}\""}"#, r#"{"a": "x"}"#]);
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert!(parse_json("").is_none());
+        assert!(parse_json("[]").is_none());
+    }
+}
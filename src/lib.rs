@@ -1,10 +1,21 @@
 pub mod column;
+pub mod compress;
+pub mod conninfo;
 pub mod db;
+pub mod delimited;
+pub mod detect;
 pub mod export;
 pub mod handlers;
+pub mod history;
+pub mod json;
+pub mod markdown;
 pub mod parser;
+pub mod plan;
 pub mod render;
+pub mod schema;
 pub mod state;
 pub mod streaming;
+pub mod tls;
+pub mod types;
 pub mod update;
 pub mod workspace;
@@ -0,0 +1,115 @@
+//! Markdown table input parsing.
+//!
+//! Parses GitHub-style pipe tables:
+//! ```text
+//! | id | name  |
+//! |----|:------|
+//! | 1  | Alice |
+//! ```
+//! Leading/trailing pipes are optional and `\|` escapes a literal pipe.
+
+use crate::parser::trim_line_ending;
+
+/// Check whether a line is a markdown header separator (e.g., `|---|:---:|`).
+pub fn is_markdown_separator(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.contains('-')
+        && trimmed.contains('|')
+        && trimmed
+            .chars()
+            .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+}
+
+/// Split a markdown table row into trimmed cells.
+fn split_markdown_row(line: &str) -> Vec<String> {
+    let trimmed = trim_line_ending(line).trim();
+    let trimmed = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let trimmed = match trimmed.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => trimmed,
+    };
+
+    let mut cells = Vec::new();
+    let mut current = String::new();
+    let mut chars = trimmed.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                current.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut current).trim().to_string()),
+            _ => current.push(c),
+        }
+    }
+    cells.push(current.trim().to_string());
+    cells
+}
+
+/// Parse markdown table header from the first few lines of input.
+///
+/// Returns `Some((headers, data_start_index))`, or `None` if the header row
+/// is not followed by a separator line.
+pub fn parse_markdown_header(lines: &[&str]) -> Option<(Vec<String>, usize)> {
+    let (header_idx, header_line) = lines
+        .iter()
+        .enumerate()
+        .find(|(_, line)| !line.trim().is_empty())?;
+
+    let separator_idx = header_idx + 1;
+    if !is_markdown_separator(lines.get(separator_idx)?) {
+        return None;
+    }
+
+    let headers = split_markdown_row(header_line);
+    if headers.iter().all(|h| h.is_empty()) {
+        return None;
+    }
+
+    Some((headers, separator_idx + 1))
+}
+
+/// Parse a single markdown table row.
+///
+/// Returns `None` for empty lines.
+pub fn parse_markdown_line(line: &str) -> Option<Vec<String>> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    Some(split_markdown_row(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_markdown_header() {
+        let lines = vec!["| id | name  |", "|----|:-----:|", "| 1  | Alice |"];
+
+        let (headers, data_start_index) = parse_markdown_header(&lines).unwrap();
+        assert_eq!(headers, vec!["id", "name"]);
+        assert_eq!(data_start_index, 2);
+    }
+
+    #[test]
+    fn test_parse_markdown_without_outer_pipes() {
+        let lines = vec!["id | name", "--- | ---", "1 | Alice"];
+
+        let (headers, _) = parse_markdown_header(&lines).unwrap();
+        assert_eq!(headers, vec!["id", "name"]);
+        assert_eq!(parse_markdown_line(lines[2]).unwrap(), vec!["1", "Alice"]);
+    }
+
+    #[test]
+    fn test_parse_markdown_escaped_pipe() {
+        let row = parse_markdown_line("| 1 | a \\| b |").unwrap();
+        assert_eq!(row, vec!["1", "a | b"]);
+    }
+
+    #[test]
+    fn test_parse_markdown_no_separator() {
+        let lines = vec!["| id | name |", "| 1  | Alice |"];
+        assert!(parse_markdown_header(&lines).is_none());
+    }
+}
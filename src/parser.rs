@@ -1,4 +1,106 @@
-use lasso::{Rodeo, Spur};
+use lasso::{Key, Rodeo, Spur};
+use std::path::Path;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::types::{ColumnType, ColumnTypes};
+
+/// Input formats accepted for piped data.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum InputFormat {
+    /// psql aligned output (the default `psql` table format)
+    Psql,
+    /// psql unaligned output (`psql -A`), fields separated by `|`
+    PsqlUnaligned,
+    /// psql expanded display (`\x`), one `-[ RECORD n ]` block per row
+    PsqlExpanded,
+    /// Comma-separated values with a header record
+    Csv,
+    /// Tab-separated values with a header record
+    Tsv,
+    /// JSON array of objects
+    Json,
+    /// Newline-delimited JSON objects
+    Ndjson,
+    /// Markdown pipe table
+    Markdown,
+}
+
+impl InputFormat {
+    /// Default field delimiter for delimited formats (None for everything else).
+    pub fn default_delimiter(self) -> Option<u8> {
+        match self {
+            InputFormat::Csv => Some(b','),
+            InputFormat::Tsv => Some(b'\t'),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from a file extension (e.g., `data.csv`, `events.jsonl`).
+    ///
+    /// A compression extension is skipped (`export.csv.gz` is CSV).
+    /// Returns `None` for unknown extensions, leaving the format to content detection.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let mut extension = path.extension()?.to_str()?;
+        if crate::compress::is_compressed_extension(extension) {
+            extension = Path::new(path.file_stem()?).extension()?.to_str()?;
+        }
+        let extension = extension.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(InputFormat::Csv),
+            "tsv" | "tab" => Some(InputFormat::Tsv),
+            "json" => Some(InputFormat::Json),
+            "ndjson" | "jsonl" => Some(InputFormat::Ndjson),
+            "md" | "markdown" => Some(InputFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    /// Human-readable name for status messages.
+    pub fn label(self) -> &'static str {
+        match self {
+            InputFormat::Psql => "psql",
+            InputFormat::PsqlUnaligned => "psql unaligned",
+            InputFormat::PsqlExpanded => "psql expanded",
+            InputFormat::Csv => "CSV",
+            InputFormat::Tsv => "TSV",
+            InputFormat::Json => "JSON",
+            InputFormat::Ndjson => "NDJSON",
+            InputFormat::Markdown => "markdown",
+        }
+    }
+}
+
+/// Index of the symbol reserved for NULL cells (the last key a `Spur` can hold)
+const NULL_KEY: usize = u32::MAX as usize - 1;
+
+/// Symbol that marks SQL NULL (or JSON `null`) in `TableData::rows`, as opposed
+/// to an empty string.
+///
+/// It is never handed out by an interner, so no value can collide with it.
+/// Check cells with `TableData::is_null` or `TableData::value`.
+pub fn null_cell() -> Spur {
+    Spur::try_from_usize(NULL_KEY).expect("NULL key fits in a Spur")
+}
+
+/// Text shown in the table view for NULL cells.
+pub const NULL_DISPLAY: &str = "NULL";
+
+/// Intern a cell value, or return the NULL symbol for `None`.
+pub fn intern_cell(interner: &mut Rodeo, cell: Option<&str>) -> Spur {
+    cell.map_or_else(null_cell, |value| interner.get_or_intern(value))
+}
+
+/// Wrap every cell of a row from a format without NULL as a present value.
+pub fn present_cells(row: Vec<String>) -> Vec<Option<String>> {
+    row.into_iter().map(Some).collect()
+}
+
+/// Turn cells equal to `null_string` (psql's `\pset null`) into NULL.
+pub fn mark_nulls(row: Vec<Option<String>>, null_string: &str) -> Vec<Option<String>> {
+    row.into_iter()
+        .map(|cell| cell.filter(|value| value != null_string))
+        .collect()
+}
 
 /// Represents parsed table data from psql output.
 pub struct TableData {
@@ -8,9 +110,57 @@ pub struct TableData {
     pub rows: Vec<Vec<Spur>>,
     /// String interner for this table's data
     pub interner: Rodeo,
+    /// Column types, declared by the source or inferred from the rows
+    pub column_types: ColumnTypes,
 }
 
 impl TableData {
+    /// Create a table and infer its column types from the rows.
+    pub fn new(headers: Vec<String>, rows: Vec<Vec<Spur>>, interner: Rodeo) -> Self {
+        let mut data = TableData {
+            headers,
+            rows,
+            interner,
+            column_types: ColumnTypes::default(),
+        };
+        data.infer_column_types();
+        data
+    }
+
+    /// Create a table with column types declared by the source (e.g., PostgreSQL).
+    pub fn with_column_types(
+        headers: Vec<String>,
+        rows: Vec<Vec<Spur>>,
+        interner: Rodeo,
+        column_types: ColumnTypes,
+    ) -> Self {
+        TableData {
+            headers,
+            rows,
+            interner,
+            column_types,
+        }
+    }
+
+    /// Type of a column (`Text` when unknown).
+    pub fn column_type(&self, col: usize) -> ColumnType {
+        self.column_types.get(col)
+    }
+
+    /// Type name of a column as declared by the source (e.g., `varchar`),
+    /// or the inferred type's label.
+    pub fn column_type_name(&self, col: usize) -> &str {
+        self.column_types.type_name(col)
+    }
+
+    /// Refine inferred column types with rows appended since the last call.
+    ///
+    /// Call after appending rows or headers; declared types are kept as is.
+    pub fn infer_column_types(&mut self) {
+        self.column_types
+            .infer(self.headers.len(), &self.rows, &self.interner);
+    }
+
     /// Returns the number of columns in the table.
     #[allow(dead_code)]
     pub fn column_count(&self) -> usize {
@@ -24,8 +174,24 @@ impl TableData {
     }
 
     /// Resolve a Spur symbol to its string value.
+    ///
+    /// NULL cells resolve to an empty string; use `value` to tell them apart.
     pub fn resolve(&self, spur: &Spur) -> &str {
-        self.interner.resolve(spur)
+        self.value(spur).unwrap_or("")
+    }
+
+    /// Check whether a cell is NULL.
+    pub fn is_null(&self, spur: &Spur) -> bool {
+        *spur == null_cell()
+    }
+
+    /// Resolve a cell to its value, or `None` for NULL.
+    pub fn value(&self, spur: &Spur) -> Option<&str> {
+        if self.is_null(spur) {
+            None
+        } else {
+            Some(self.interner.resolve(spur))
+        }
     }
 
     /// Resolve all symbols in a row to owned Strings.
@@ -44,10 +210,7 @@ impl Clone for TableData {
             .iter()
             .map(|row| {
                 row.iter()
-                    .map(|spur| {
-                        let s = self.interner.resolve(spur);
-                        new_interner.get_or_intern(s)
-                    })
+                    .map(|spur| intern_cell(&mut new_interner, self.value(spur)))
                     .collect()
             })
             .collect();
@@ -55,6 +218,7 @@ impl Clone for TableData {
             headers: self.headers.clone(),
             rows: new_rows,
             interner: new_interner,
+            column_types: self.column_types.clone(),
         }
     }
 }
@@ -68,6 +232,141 @@ impl std::fmt::Debug for TableData {
     }
 }
 
+/// How a malformed line was handled while parsing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IssueAction {
+    /// Missing cells were filled with empty values (e.g., a truncated last line)
+    Padded,
+    /// Extra cells were joined into the last column
+    Merged,
+    /// The line has no column separators and was skipped (e.g., a NOTICE message)
+    Rejected,
+}
+
+impl IssueAction {
+    /// Human-readable name for the issues view.
+    pub fn label(self) -> &'static str {
+        match self {
+            IssueAction::Padded => "padded",
+            IssueAction::Merged => "merged",
+            IssueAction::Rejected => "rejected",
+        }
+    }
+}
+
+/// A data line whose cell count did not match the header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseIssue {
+    /// Line number in the input (1-based)
+    pub line_number: usize,
+    /// Number of columns in the header
+    pub expected: usize,
+    /// Number of cells found on the line
+    pub actual: usize,
+    /// Raw text of the line
+    pub text: String,
+    /// Whether the line was repaired or rejected
+    pub action: IssueAction,
+}
+
+/// Build a table listing parse issues, so they can be shown in a regular tab.
+pub fn issues_table(issues: &[ParseIssue]) -> TableData {
+    let mut interner = Rodeo::default();
+    let rows = issues
+        .iter()
+        .map(|issue| {
+            [
+                issue.line_number.to_string(),
+                issue.action.label().to_string(),
+                issue.expected.to_string(),
+                issue.actual.to_string(),
+                issue.text.clone(),
+            ]
+            .iter()
+            .map(|cell| interner.get_or_intern(cell))
+            .collect()
+        })
+        .collect();
+
+    TableData::new(
+        ["line", "action", "expected", "actual", "text"]
+            .iter()
+            .map(|h| h.to_string())
+            .collect(),
+        rows,
+        interner,
+    )
+}
+
+/// Column boundaries of psql aligned output, taken from the `----+----` separator line.
+///
+/// Values can contain `|`, so rows are sliced at the display columns where the
+/// separator has a `+` instead of splitting on every pipe.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColumnBoundaries {
+    /// Display column of each `+` in the separator line
+    positions: Vec<usize>,
+}
+
+impl ColumnBoundaries {
+    /// Read column boundaries from a separator line (e.g., `----+-------+-----`).
+    pub fn from_separator(separator_line: &str) -> Self {
+        ColumnBoundaries {
+            positions: separator_line
+                .chars()
+                .enumerate()
+                .filter(|(_, c)| *c == '+')
+                .map(|(col, _)| col)
+                .collect(),
+        }
+    }
+
+    /// Number of columns described by the separator.
+    pub fn column_count(&self) -> usize {
+        self.positions.len() + 1
+    }
+
+    /// Split a line into one segment per column.
+    ///
+    /// Falls back to splitting on every `|` when the line does not have a `|`
+    /// at each boundary (e.g., hand-edited output or unusual character widths).
+    pub fn split<'a>(&self, line: &'a str) -> Vec<&'a str> {
+        self.split_at_boundaries(line)
+            .unwrap_or_else(|| line.split('|').collect())
+    }
+
+    fn split_at_boundaries<'a>(&self, line: &'a str) -> Option<Vec<&'a str>> {
+        let mut segments = Vec::with_capacity(self.column_count());
+        let mut targets = self.positions.iter().peekable();
+        let mut segment_start = 0;
+        let mut col = 0;
+
+        for (idx, c) in line.char_indices() {
+            let Some(&&target) = targets.peek() else {
+                break;
+            };
+            if col == target {
+                if c != '|' {
+                    return None;
+                }
+                segments.push(&line[segment_start..idx]);
+                segment_start = idx + c.len_utf8();
+                targets.next();
+            } else if col > target {
+                // A wide character straddles the boundary
+                return None;
+            }
+            col += c.width().unwrap_or(0);
+        }
+
+        if targets.peek().is_some() {
+            return None;
+        }
+        segments.push(&line[segment_start..]);
+        Some(segments)
+    }
+}
+
 /// Parse psql header from the first few lines of output.
 ///
 /// Returns `Some((headers, data_start_index))` where:
@@ -84,16 +383,6 @@ pub fn parse_psql_header(lines: &[&str]) -> Option<(Vec<String>, usize)> {
     let mut line_iter = lines.iter().enumerate();
     let (header_idx, header_line) = line_iter.find(|(_, line)| !line.trim().is_empty())?;
 
-    // Parse headers by splitting on |
-    let headers: Vec<String> = header_line
-        .split('|')
-        .map(|s| s.trim().to_string())
-        .collect();
-
-    if headers.is_empty() || headers.iter().all(|h| h.is_empty()) {
-        return None;
-    }
-
     // The next line should be the separator (contains ---)
     let separator_idx = header_idx + 1;
     if separator_idx >= lines.len() {
@@ -105,21 +394,40 @@ pub fn parse_psql_header(lines: &[&str]) -> Option<(Vec<String>, usize)> {
         return None;
     }
 
+    // Parse headers by slicing at the separator's column boundaries
+    let headers: Vec<String> = ColumnBoundaries::from_separator(separator_line)
+        .split(trim_line_ending(header_line))
+        .iter()
+        .map(|s| s.trim().to_string())
+        .collect();
+
+    if headers.is_empty() || headers.iter().all(|h| h.is_empty()) {
+        return None;
+    }
+
     // Data starts after the separator
     let data_start_index = separator_idx + 1;
 
     Some((headers, data_start_index))
 }
 
+/// Check whether a line is a psql row-count footer (e.g., "(2 rows)").
+pub fn is_psql_footer(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.starts_with('(') && trimmed.ends_with(')') && trimmed.contains("row")
+}
+
 /// Parse a single data row from psql output.
 ///
+/// Cells are sliced at the `columns` boundaries, so values containing `|` are kept whole.
+///
 /// Returns `None` for:
 /// - Empty lines
 /// - Footer lines (e.g., "(2 rows)")
 ///
-/// The `column_count` parameter is accepted but not strictly enforced
-/// to match existing parse_psql behavior.
-pub fn parse_psql_line(line: &str, _column_count: usize) -> Option<Vec<String>> {
+/// The column count is not enforced here; `AlignedRowParser` repairs or rejects
+/// rows with the wrong number of cells and records a `ParseIssue` for each.
+pub fn parse_psql_line(line: &str, columns: &ColumnBoundaries) -> Option<Vec<String>> {
     let trimmed = line.trim();
 
     // Skip empty lines
@@ -128,16 +436,351 @@ pub fn parse_psql_line(line: &str, _column_count: usize) -> Option<Vec<String>>
     }
 
     // Skip footer lines (e.g., "(2 rows)")
-    if trimmed.starts_with('(') && trimmed.ends_with(')') && trimmed.contains("row") {
+    if is_psql_footer(trimmed) {
         return None;
     }
 
-    // Parse row by splitting on |
-    let row: Vec<String> = line.split('|').map(|s| s.trim().to_string()).collect();
+    // Parse row by slicing at the column boundaries
+    let row: Vec<String> = columns
+        .split(trim_line_ending(line))
+        .iter()
+        .map(|s| s.trim().to_string())
+        .collect();
 
     Some(row)
 }
 
+/// Assembles psql aligned output lines into rows, joining continuation lines.
+///
+/// psql marks values that continue on the next physical line in the right
+/// margin of each cell: `+` for an embedded newline and `.` for a value
+/// wrapped by `\pset format wrapped`:
+/// ```text
+///  id |  note
+/// ----+--------
+///  1  | first +
+///     | second
+///  2  | plain
+/// ```
+/// The last column has no `|` after it, so its marker only counts when the
+/// line is as wide as the separator line (psql pads the cell before the marker).
+///
+/// Rows with the wrong number of cells are repaired (padded, or extra cells
+/// merged into the last column) and lines without any column separator are
+/// skipped. Each is recorded as a `ParseIssue`, see `take_issues`.
+#[derive(Debug, Default)]
+pub struct AlignedRowParser {
+    /// Column boundaries from the `----+----` separator line
+    columns: ColumnBoundaries,
+    /// Display width of the separator line
+    line_width: usize,
+    /// Row being continued, with the continuation marker of each cell
+    pending: Option<(Vec<String>, Vec<char>)>,
+    /// Line number of the last line read (1-based)
+    line_number: usize,
+    /// Malformed lines found since the last `take_issues`
+    issues: Vec<ParseIssue>,
+}
+
+impl AlignedRowParser {
+    /// Create a parser for rows below the given separator line.
+    ///
+    /// `separator_line_number` is the 1-based line number of the separator,
+    /// used to number the lines in parse issues.
+    pub fn new(separator_line: &str, separator_line_number: usize) -> Self {
+        AlignedRowParser {
+            columns: ColumnBoundaries::from_separator(separator_line),
+            line_width: separator_line.trim_end().width(),
+            pending: None,
+            line_number: separator_line_number,
+            issues: Vec::new(),
+        }
+    }
+
+    /// Take the parse issues found since the last call.
+    pub fn take_issues(&mut self) -> Vec<ParseIssue> {
+        std::mem::take(&mut self.issues)
+    }
+
+    /// Whether the last row continues on the next line.
+    pub fn is_continuing(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Feed one line of input, returning the row once all its lines are read.
+    ///
+    /// Empty and footer lines are skipped between rows.
+    pub fn push_line(&mut self, line: &str) -> Option<Vec<String>> {
+        self.line_number += 1;
+        let line = trim_line_ending(line);
+        if !self.is_continuing() && (line.trim().is_empty() || is_psql_footer(line)) {
+            return None;
+        }
+
+        let (mut cells, mut markers) = self.split_cells(line);
+        if !self.is_continuing() && !self.check_cell_count(line, &mut cells, &mut markers) {
+            return None;
+        }
+
+        let row = match self.pending.take() {
+            Some((mut row, prev_markers)) => {
+                for (i, cell) in cells.into_iter().enumerate() {
+                    match (row.get_mut(i), prev_markers.get(i)) {
+                        (Some(existing), Some('+')) => {
+                            existing.push('\n');
+                            existing.push_str(&cell);
+                        }
+                        (Some(existing), Some('.')) => existing.push_str(&cell),
+                        // This cell ended on an earlier line
+                        (Some(_), _) => {}
+                        (None, _) => row.push(cell),
+                    }
+                }
+                row
+            }
+            None => cells,
+        };
+
+        if markers.iter().any(|&m| m != ' ') {
+            self.pending = Some((row, markers));
+            None
+        } else {
+            Some(row)
+        }
+    }
+
+    /// Flush a row left unfinished at end of input.
+    pub fn finish(&mut self) -> Option<Vec<String>> {
+        self.pending.take().map(|(row, _)| row)
+    }
+
+    /// Check the number of cells on the first line of a row, repairing it if needed.
+    ///
+    /// Returns false if the line is not a row at all and should be skipped.
+    fn check_cell_count(
+        &mut self,
+        line: &str,
+        cells: &mut Vec<String>,
+        markers: &mut Vec<char>,
+    ) -> bool {
+        let expected = self.columns.column_count();
+        let actual = cells.len();
+        if actual == expected {
+            return true;
+        }
+
+        let action = if actual == 1 {
+            IssueAction::Rejected
+        } else if actual < expected {
+            cells.resize(expected, String::new());
+            markers.resize(expected, ' ');
+            IssueAction::Padded
+        } else {
+            // Keep the pipes of the extra cells as part of the last value
+            *cells = line
+                .splitn(expected, '|')
+                .map(|s| s.trim().to_string())
+                .collect();
+            *markers = vec![' '; expected];
+            IssueAction::Merged
+        };
+
+        self.issues.push(ParseIssue {
+            line_number: self.line_number,
+            expected,
+            actual,
+            text: line.to_string(),
+            action,
+        });
+        action != IssueAction::Rejected
+    }
+
+    /// Split a line into cell text and the continuation marker of each cell.
+    fn split_cells(&self, line: &str) -> (Vec<String>, Vec<char>) {
+        let prev_markers = self
+            .pending
+            .as_ref()
+            .map(|(_, markers)| markers.as_slice())
+            .unwrap_or(&[]);
+        let full_width = self.line_width > 0 && line.width() >= self.line_width;
+
+        let segments = self.columns.split(line);
+        let last = segments.len() - 1;
+        let mut cells = Vec::with_capacity(segments.len());
+        let mut markers = Vec::with_capacity(segments.len());
+
+        for (i, segment) in segments.into_iter().enumerate() {
+            // A wrapped value continues after a `.` in the left margin
+            let continued = prev_markers.get(i) == Some(&'.');
+            let mut text = if continued {
+                segment.strip_prefix('.').unwrap_or(segment)
+            } else {
+                segment
+            };
+
+            let marker = match text.chars().last() {
+                Some(c @ ('+' | '.')) if i < last || full_width => c,
+                _ => ' ',
+            };
+            if marker != ' ' {
+                text = &text[..text.len() - 1];
+            }
+
+            // Wrapped pieces keep spaces at the wrap point
+            let cell = match (continued, marker == '.') {
+                (false, false) => text.trim(),
+                (false, true) => text.trim_start(),
+                (true, false) => text.trim_end(),
+                (true, true) => text,
+            };
+            cells.push(cell.to_string());
+            markers.push(marker);
+        }
+
+        (cells, markers)
+    }
+}
+
+/// Parse psql unaligned header (`psql -A`) from the first few lines of output.
+///
+/// Unaligned output has no separator line, so the first non-empty line is the
+/// header and data starts on the next line.
+///
+/// Returns `Some((headers, data_start_index))`, or `None` if there is no header line.
+pub fn parse_psql_unaligned_header(lines: &[&str]) -> Option<(Vec<String>, usize)> {
+    let (header_idx, header_line) = lines
+        .iter()
+        .enumerate()
+        .find(|(_, line)| !line.trim().is_empty())?;
+
+    let headers: Vec<String> = trim_line_ending(header_line)
+        .split('|')
+        .map(|s| s.to_string())
+        .collect();
+
+    if headers.iter().all(|h| h.trim().is_empty()) {
+        return None;
+    }
+
+    Some((headers, header_idx + 1))
+}
+
+/// Parse a single data row from psql unaligned output.
+///
+/// Values are not padded in unaligned mode, so cells are kept verbatim
+/// (only the line ending is removed). Empty and footer lines return `None`.
+pub fn parse_psql_unaligned_line(line: &str) -> Option<Vec<String>> {
+    if line.trim().is_empty() || is_psql_footer(line) {
+        return None;
+    }
+
+    Some(
+        trim_line_ending(line)
+            .split('|')
+            .map(|s| s.to_string())
+            .collect(),
+    )
+}
+
+/// Check whether a line starts a psql expanded record (e.g., `-[ RECORD 1 ]----`).
+///
+/// Also matches the `+-[ RECORD 1 ]-+` form printed with `\pset border 2`.
+pub fn is_expanded_record_header(line: &str) -> bool {
+    line.trim()
+        .trim_start_matches('+')
+        .starts_with("-[ RECORD ")
+}
+
+/// Assembles psql expanded display (`\x`) output into rows.
+///
+/// Expanded output prints each row as a block of `field | value` lines:
+/// ```text
+/// -[ RECORD 1 ]-----
+/// id   | 1
+/// name | Alice
+/// -[ RECORD 2 ]-----
+/// id   | 2
+/// name | Bob
+/// ```
+/// A row is complete when the next record header (or the end of input) is seen.
+/// Lines with a blank field name continue the previous value, as psql prints
+/// for values containing newlines.
+/// Fields are matched to columns by position, so duplicate field names are kept.
+#[derive(Debug, Default)]
+pub struct ExpandedRecordParser {
+    /// Field names in the order they appear in a record
+    headers: Vec<String>,
+    /// Cells of the record currently being read
+    current: Option<Vec<String>>,
+}
+
+impl ExpandedRecordParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Column names seen so far.
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    /// Feed one line of input, returning the previous record once a new one starts.
+    pub fn push_line(&mut self, line: &str) -> Option<Vec<String>> {
+        if is_expanded_record_header(line) {
+            return self.current.replace(Vec::new());
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('+') || is_psql_footer(trimmed) {
+            return None;
+        }
+
+        let cells = self.current.as_mut()?;
+
+        // Border 2 wraps each field line in outer pipes (continuation lines
+        // in border 1 also start with `|`, but only after indentation)
+        let field = match trim_line_ending(line).strip_prefix('|') {
+            Some(inner) => inner.trim_end().strip_suffix('|').unwrap_or(inner),
+            None => trimmed,
+        };
+        let (name, value) = field.split_once('|')?;
+
+        // A blank field name continues the previous value (`+` newline, `.` wrap)
+        if name.trim().is_empty() {
+            if let Some(previous) = cells.last_mut() {
+                if previous.ends_with('.') {
+                    previous.pop();
+                } else {
+                    if previous.ends_with('+') {
+                        previous.pop();
+                    }
+                    previous.truncate(previous.trim_end().len());
+                    previous.push('\n');
+                }
+                previous.push_str(value.trim());
+                return None;
+            }
+        }
+
+        if cells.len() == self.headers.len() {
+            self.headers.push(name.trim().to_string());
+        }
+        cells.push(value.trim().to_string());
+
+        None
+    }
+
+    /// Flush the last record at end of input.
+    pub fn finish(&mut self) -> Option<Vec<String>> {
+        self.current.take()
+    }
+}
+
+/// Strip a trailing `\n` or `\r\n` from a line read with `read_line`.
+pub fn trim_line_ending(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
 /// Parse psql output format into structured TableData.
 ///
 /// Expected format:
@@ -149,8 +792,19 @@ pub fn parse_psql_line(line: &str, _column_count: usize) -> Option<Vec<String>>
 /// (2 rows)
 /// ```
 ///
+/// Expanded display output (`\x`) is also accepted, see `parse_psql_expanded`.
+///
 /// Returns `None` if input is empty or malformed.
 pub fn parse_psql(input: &str) -> Option<TableData> {
+    parse_psql_with_issues(input).map(|(table, _)| table)
+}
+
+/// Parse psql output like `parse_psql`, also returning the malformed data lines.
+///
+/// Rows with missing cells are padded, extra cells are merged into the last
+/// column, and lines without column separators (e.g., `NOTICE:` messages) are
+/// skipped. Each is reported as a `ParseIssue`.
+pub fn parse_psql_with_issues(input: &str) -> Option<(TableData, Vec<ParseIssue>)> {
     let lines: Vec<&str> = input.lines().collect();
 
     if lines.is_empty() {
@@ -161,14 +815,8 @@ pub fn parse_psql(input: &str) -> Option<TableData> {
     let mut line_iter = lines.iter().enumerate();
     let (header_idx, header_line) = line_iter.find(|(_, line)| !line.trim().is_empty())?;
 
-    // Parse headers by splitting on |
-    let headers: Vec<String> = header_line
-        .split('|')
-        .map(|s| s.trim().to_string())
-        .collect();
-
-    if headers.is_empty() || headers.iter().all(|h| h.is_empty()) {
-        return None;
+    if is_expanded_record_header(header_line) {
+        return parse_psql_expanded(input).map(|table| (table, Vec::new()));
     }
 
     // The next line should be the separator (contains ---)
@@ -182,37 +830,81 @@ pub fn parse_psql(input: &str) -> Option<TableData> {
         return None;
     }
 
+    // Parse headers by slicing at the separator's column boundaries
+    let headers: Vec<String> = ColumnBoundaries::from_separator(separator_line)
+        .split(header_line)
+        .iter()
+        .map(|s| s.trim().to_string())
+        .collect();
+
+    if headers.is_empty() || headers.iter().all(|h| h.is_empty()) {
+        return None;
+    }
+
     // Parse data rows (everything after separator until footer)
     let mut interner = Rodeo::default();
     let mut rows: Vec<Vec<Spur>> = Vec::new();
+    let mut row_parser = AlignedRowParser::new(separator_line, separator_idx + 1);
 
     for line in lines.iter().skip(separator_idx + 1) {
-        let trimmed = line.trim();
-
-        // Skip empty lines
-        if trimmed.is_empty() {
-            continue;
-        }
-
         // Stop at footer line (e.g., "(2 rows)")
-        if trimmed.starts_with('(') && trimmed.ends_with(')') && trimmed.contains("row") {
+        if !row_parser.is_continuing() && is_psql_footer(line) {
             break;
         }
 
-        // Parse row by splitting on | and interning each cell
-        let row: Vec<Spur> = line
-            .split('|')
-            .map(|s| interner.get_or_intern(s.trim()))
-            .collect();
+        // Intern each cell once all lines of the row are read
+        if let Some(row) = row_parser.push_line(line) {
+            rows.push(row.iter().map(|s| interner.get_or_intern(s)).collect());
+        }
+    }
 
-        rows.push(row);
+    if let Some(row) = row_parser.finish() {
+        rows.push(row.iter().map(|s| interner.get_or_intern(s)).collect());
     }
 
-    Some(TableData {
-        headers,
-        rows,
-        interner,
-    })
+    let table = TableData::new(headers, rows, interner);
+    Some((table, row_parser.take_issues()))
+}
+
+/// Parse psql expanded display output (`\x`) into structured TableData.
+///
+/// Expected format:
+/// ```text
+/// -[ RECORD 1 ]-----
+/// id   | 1
+/// name | Alice
+/// -[ RECORD 2 ]-----
+/// id   | 2
+/// name | Bob
+/// ```
+///
+/// Each record becomes one row and the field names become the columns.
+/// Returns `None` if input contains no records.
+pub fn parse_psql_expanded(input: &str) -> Option<TableData> {
+    let mut parser = ExpandedRecordParser::new();
+    let mut interner = Rodeo::default();
+    let mut rows: Vec<Vec<Spur>> = Vec::new();
+
+    let mut records: Vec<Vec<String>> = input
+        .lines()
+        .filter_map(|line| parser.push_line(line))
+        .collect();
+    records.extend(parser.finish());
+
+    for record in records {
+        rows.push(
+            record
+                .iter()
+                .map(|cell| interner.get_or_intern(cell))
+                .collect(),
+        );
+    }
+
+    if rows.is_empty() {
+        return None;
+    }
+
+    Some(TableData::new(parser.headers, rows, interner))
 }
 
 #[cfg(test)]
@@ -249,6 +941,31 @@ mod tests {
         assert_eq!(row1, vec!["2", "Bob", "25"]);
     }
 
+    #[test]
+    fn test_parse_infers_column_types() {
+        let input = " id | price | active |  created   | note
+----+-------+--------+------------+------
+  1 |  9.99 | t      | 2024-01-31 | a
+  2 | 12    | f      |            | 7
+(2 rows)";
+
+        let table = parse_psql(input).unwrap();
+        let types: Vec<ColumnType> = (0..table.column_count())
+            .map(|col| table.column_type(col))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ColumnType::Integer,
+                ColumnType::Decimal,
+                ColumnType::Boolean,
+                ColumnType::Date,
+                ColumnType::Text,
+            ]
+        );
+        assert!(!table.column_types.is_declared());
+    }
+
     #[test]
     fn test_parse_single_row() {
         let input = " a | b
@@ -363,24 +1080,259 @@ mod tests {
     #[test]
     fn test_parse_psql_line_data_row() {
         let line = " 1  | Alice | 30";
-        let result = parse_psql_line(line, 3);
+        let result = parse_psql_line(
+            line,
+            &ColumnBoundaries::from_separator("----+-------+-----"),
+        );
         assert!(result.is_some());
 
         let row = result.unwrap();
         assert_eq!(row, vec!["1", "Alice", "30"]);
     }
 
+    #[test]
+    fn test_parse_psql_line_embedded_pipe() {
+        let columns = ColumnBoundaries::from_separator("----+-------+-----");
+        assert_eq!(columns.column_count(), 3);
+
+        let row = parse_psql_line(" 1  | a|b   | 30", &columns).unwrap();
+        assert_eq!(row, vec!["1", "a|b", "30"]);
+
+        // Lines that do not line up with the separator are split on every pipe
+        let row = parse_psql_line("1|Alice|30", &columns).unwrap();
+        assert_eq!(row, vec!["1", "Alice", "30"]);
+    }
+
     #[test]
     fn test_parse_psql_line_footer() {
         let line = "(2 rows)";
-        let result = parse_psql_line(line, 3);
+        let result = parse_psql_line(
+            line,
+            &ColumnBoundaries::from_separator("----+-------+-----"),
+        );
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_input_format_from_path() {
+        assert_eq!(
+            InputFormat::from_path(Path::new("dump.CSV")),
+            Some(InputFormat::Csv)
+        );
+        assert_eq!(
+            InputFormat::from_path(Path::new("/tmp/events.jsonl")),
+            Some(InputFormat::Ndjson)
+        );
+        assert_eq!(
+            InputFormat::from_path(Path::new("nightly.tsv.gz")),
+            Some(InputFormat::Tsv)
+        );
+        assert_eq!(InputFormat::from_path(Path::new("psql.txt")), None);
+        assert_eq!(InputFormat::from_path(Path::new("psql.txt.zst")), None);
+        assert_eq!(InputFormat::from_path(Path::new("noext")), None);
+    }
+
+    #[test]
+    fn test_parse_psql_unaligned() {
+        let lines = vec!["id|name|note\n", "1|Alice| padded \n", "(1 row)\n"];
+
+        let (headers, data_start_index) = parse_psql_unaligned_header(&lines).unwrap();
+        assert_eq!(headers, vec!["id", "name", "note"]);
+        assert_eq!(data_start_index, 1);
+
+        let row = parse_psql_unaligned_line(lines[1]).unwrap();
+        assert_eq!(row, vec!["1", "Alice", " padded "]);
+        assert!(parse_psql_unaligned_line(lines[2]).is_none());
+    }
+
+    #[test]
+    fn test_parse_multiline_cells() {
+        let input = " id |  note  | tag
+----+--------+-----
+  1 | first +| a
+    | second | 
+  2 | plain  | b
+(2 rows)";
+
+        let table = parse_psql(input).unwrap();
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(
+            table.resolve_row(&table.rows[0]),
+            vec!["1", "first\nsecond", "a"]
+        );
+        assert_eq!(table.resolve_row(&table.rows[1]), vec!["2", "plain", "b"]);
+    }
+
+    #[test]
+    fn test_parse_multiline_last_column() {
+        let input = " id |  note
+----+--------
+  1 | first +
+    | second
+  2 | a+
+(2 rows)";
+
+        let table = parse_psql(input).unwrap();
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(table.resolve(&table.rows[0][1]), "first\nsecond");
+        // A trailing `+` in a value narrower than the column is not a marker
+        assert_eq!(table.resolve(&table.rows[1][1]), "a+");
+    }
+
+    #[test]
+    fn test_parse_wrapped_cells() {
+        let input = " id |   note
+----+----------
+  1 | abcdefgh.
+    |.ij
+  2 | short
+";
+
+        let table = parse_psql(input).unwrap();
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(table.resolve(&table.rows[0][1]), "abcdefghij");
+        assert_eq!(table.resolve(&table.rows[1][1]), "short");
+    }
+
+    #[test]
+    fn test_parse_psql_expanded_multiline() {
+        let input = "-[ RECORD 1 ]-----
+id   | 1
+note | first +
+     | second
+tag  | a
+";
+
+        let table = parse_psql_expanded(input).unwrap();
+        assert_eq!(table.headers, vec!["id", "note", "tag"]);
+        assert_eq!(
+            table.resolve_row(&table.rows[0]),
+            vec!["1", "first\nsecond", "a"]
+        );
+    }
+
+    #[test]
+    fn test_parse_psql_expanded() {
+        let input = "-[ RECORD 1 ]-----
+id   | 1
+name | Alice
+note |
+-[ RECORD 2 ]-----
+id   | 2
+name | Bob
+note | a | b
+";
+
+        let table = parse_psql(input).unwrap();
+        assert_eq!(table.headers, vec!["id", "name", "note"]);
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(table.resolve_row(&table.rows[0]), vec!["1", "Alice", ""]);
+        assert_eq!(table.resolve_row(&table.rows[1]), vec!["2", "Bob", "a | b"]);
+    }
+
+    #[test]
+    fn test_parse_psql_expanded_border_2() {
+        let input = "+-[ RECORD 1 ]-+
+| id   | 1     |
+| name | Alice |
++------+-------+
+";
+
+        let table = parse_psql_expanded(input).unwrap();
+        assert_eq!(table.headers, vec!["id", "name"]);
+        assert_eq!(table.resolve_row(&table.rows[0]), vec!["1", "Alice"]);
+    }
+
+    #[test]
+    fn test_expanded_record_parser_streaming() {
+        let mut parser = ExpandedRecordParser::new();
+        assert_eq!(parser.push_line("-[ RECORD 1 ]--"), None);
+        assert_eq!(parser.push_line("?column? | 1"), None);
+        assert_eq!(parser.push_line("?column? | 2"), None);
+        assert_eq!(
+            parser.push_line("-[ RECORD 2 ]--"),
+            Some(vec!["1".to_string(), "2".to_string()])
+        );
+        assert_eq!(parser.push_line("?column? | 3"), None);
+        assert_eq!(parser.finish(), Some(vec!["3".to_string()]));
+        assert_eq!(parser.headers(), ["?column?", "?column?"]);
+    }
+
     #[test]
     fn test_parse_psql_line_empty() {
         let line = "   ";
-        let result = parse_psql_line(line, 3);
+        let result = parse_psql_line(
+            line,
+            &ColumnBoundaries::from_separator("----+-------+-----"),
+        );
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_parse_psql_malformed_rows() {
+        let input = " id | name  | city\n\
+                     ----+-------+------\n\
+                     \x20 1 | Alice | NYC\n\
+                     NOTICE:  table \"tmp\" does not exist, skipping\n\
+                     \x20 2 | Bob\n\
+                     3|Carol|a|b\n\
+                     (3 rows)\n";
+
+        let (table, issues) = parse_psql_with_issues(input).unwrap();
+        let rows: Vec<Vec<String>> = table.rows.iter().map(|r| table.resolve_row(r)).collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["1", "Alice", "NYC"],
+                vec!["2", "Bob", ""],
+                vec!["3", "Carol", "a|b"],
+            ]
+        );
+
+        let summary: Vec<(usize, IssueAction, usize)> = issues
+            .iter()
+            .map(|i| (i.line_number, i.action, i.actual))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (4, IssueAction::Rejected, 1),
+                (5, IssueAction::Padded, 2),
+                (6, IssueAction::Merged, 4),
+            ]
+        );
+        assert!(issues.iter().all(|i| i.expected == 3));
+        assert_eq!(
+            issues[0].text,
+            "NOTICE:  table \"tmp\" does not exist, skipping"
+        );
+    }
+
+    #[test]
+    fn test_parse_psql_well_formed_has_no_issues() {
+        let input = " id | name\n----+------\n  1 | a|b\n(1 row)\n";
+        let (_, issues) = parse_psql_with_issues(input).unwrap();
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_issues_table() {
+        let issues = vec![ParseIssue {
+            line_number: 7,
+            expected: 3,
+            actual: 1,
+            text: "WARNING: x".to_string(),
+            action: IssueAction::Rejected,
+        }];
+
+        let table = issues_table(&issues);
+        assert_eq!(
+            table.headers,
+            vec!["line", "action", "expected", "actual", "text"]
+        );
+        assert_eq!(
+            table.resolve_row(&table.rows[0]),
+            vec!["7", "rejected", "3", "1", "WARNING: x"]
+        );
+    }
 }
@@ -0,0 +1,485 @@
+//! Query plans from `EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON)`.
+//!
+//! The plan is a tree of nodes shown as the rows of a regular table, with
+//! collapsible children like the schema browser. The nodes taking the most
+//! time on their own (excluding their children) are marked as expensive.
+
+use serde_json::{Map, Value};
+
+use crate::parser::TableData;
+use crate::types::{ColumnType, ColumnTypes};
+use lasso::Rodeo;
+
+/// Headers of the table the plan is shown as, with their types.
+const COLUMNS: [(&str, ColumnType); 9] = [
+    ("Node", ColumnType::Text),
+    ("Cost", ColumnType::Decimal),
+    ("Time (ms)", ColumnType::Decimal),
+    ("Self (ms)", ColumnType::Decimal),
+    ("Rows (est.)", ColumnType::Integer),
+    ("Rows", ColumnType::Integer),
+    ("Loops", ColumnType::Integer),
+    ("Estimate", ColumnType::Text),
+    ("Buffers", ColumnType::Text),
+];
+
+/// At most this many nodes are marked as expensive.
+const MAX_EXPENSIVE: usize = 3;
+
+/// Nodes taking less than this share of the total are never marked as expensive.
+const EXPENSIVE_SHARE: f64 = 0.1;
+
+/// Row estimates off by at least this factor are shown in the Estimate column.
+const MISESTIMATE_FACTOR: f64 = 2.0;
+
+/// Prefix running a query so that its plan is returned as JSON.
+pub const EXPLAIN_PREFIX: &str = "EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON) ";
+
+/// A node of the plan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanNode {
+    /// Description like psql's, e.g. "Index Scan using users_pkey on users u"
+    pub label: String,
+    pub depth: usize,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub total_cost: f64,
+    /// Rows per loop estimated by the planner
+    pub plan_rows: f64,
+    /// Time per loop in milliseconds (None without ANALYZE)
+    pub actual_time: Option<f64>,
+    /// Rows per loop (None without ANALYZE)
+    pub actual_rows: Option<f64>,
+    pub loops: Option<f64>,
+    pub shared_hit_blocks: u64,
+    pub shared_read_blocks: u64,
+    expanded: bool,
+}
+
+impl PlanNode {
+    /// Time spent in the node across all loops, in milliseconds.
+    pub fn total_time(&self) -> Option<f64> {
+        Some(self.actual_time? * self.loops?)
+    }
+}
+
+/// A query plan with the expansion state of its nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    /// Nodes in depth-first order; the root is the first
+    pub nodes: Vec<PlanNode>,
+    pub planning_time: Option<f64>,
+    pub execution_time: Option<f64>,
+    /// Time (or, without timings, cost) of each node excluding its children
+    self_cost: Vec<f64>,
+    /// Nodes marked as expensive
+    expensive: Vec<usize>,
+    /// Visible nodes, in display order
+    lines: Vec<usize>,
+}
+
+impl Plan {
+    /// Parse the output of `EXPLAIN (FORMAT JSON)`, with or without ANALYZE.
+    pub fn from_json(json: &str) -> Result<Plan, String> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|e| format!("Invalid plan JSON: {}", e))?;
+        Self::from_value(&value)
+    }
+
+    /// Build a plan from parsed `EXPLAIN (FORMAT JSON)` output.
+    pub fn from_value(value: &Value) -> Result<Plan, String> {
+        let top = value
+            .get(0)
+            .and_then(Value::as_object)
+            .ok_or("Plan JSON is not an EXPLAIN result")?;
+        let root = top
+            .get("Plan")
+            .and_then(Value::as_object)
+            .ok_or("Plan JSON has no plan")?;
+
+        let mut nodes = Vec::new();
+        add_node(&mut nodes, root, None, 0);
+        let self_cost = self_costs(&nodes);
+        let expensive = expensive_nodes(&self_cost);
+        let mut plan = Plan {
+            nodes,
+            planning_time: top.get("Planning Time").and_then(Value::as_f64),
+            execution_time: top.get("Execution Time").and_then(Value::as_f64),
+            self_cost,
+            expensive,
+            lines: Vec::new(),
+        };
+        plan.update_lines();
+        Ok(plan)
+    }
+
+    /// Planning and execution time, e.g. "Planning 0.12 ms, execution 4.20 ms".
+    pub fn summary(&self) -> String {
+        match (self.planning_time, self.execution_time) {
+            (Some(planning), Some(execution)) => {
+                format!("Planning {:.2} ms, execution {:.2} ms", planning, execution)
+            }
+            _ => format!("Estimated cost {:.2}", self.nodes[0].total_cost),
+        }
+    }
+
+    /// Node shown on the given row.
+    pub fn node(&self, line: usize) -> Option<usize> {
+        self.lines.get(line).copied()
+    }
+
+    /// Row the given node is shown on, if visible.
+    pub fn line_of(&self, node: usize) -> Option<usize> {
+        self.lines.iter().position(|n| *n == node)
+    }
+
+    /// Rows showing expensive nodes.
+    pub fn expensive_lines(&self) -> Vec<usize> {
+        self.expensive
+            .iter()
+            .filter_map(|node| self.line_of(*node))
+            .collect()
+    }
+
+    /// Check if a node is marked as expensive.
+    pub fn is_expensive(&self, node: usize) -> bool {
+        self.expensive.contains(&node)
+    }
+
+    /// Check if a node has children.
+    pub fn can_expand(&self, node: usize) -> bool {
+        !self.nodes[node].children.is_empty()
+    }
+
+    /// Check if a node's children are shown.
+    pub fn is_expanded(&self, node: usize) -> bool {
+        self.can_expand(node) && self.nodes[node].expanded
+    }
+
+    /// Show or hide a node's children. Does nothing for leaves.
+    pub fn set_expanded(&mut self, node: usize, expanded: bool) {
+        if self.can_expand(node) {
+            self.nodes[node].expanded = expanded;
+            self.update_lines();
+        }
+    }
+
+    fn update_lines(&mut self) {
+        self.lines.clear();
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            self.lines.push(node);
+            if self.nodes[node].expanded {
+                stack.extend(self.nodes[node].children.iter().rev());
+            }
+        }
+    }
+
+    /// The visible nodes as a table, one row per node.
+    pub fn table_data(&self) -> TableData {
+        let mut interner = Rodeo::default();
+        let rows = self
+            .lines
+            .iter()
+            .map(|node| {
+                self.row(*node)
+                    .iter()
+                    .map(|cell| interner.get_or_intern(cell))
+                    .collect()
+            })
+            .collect();
+        TableData::with_column_types(
+            COLUMNS.iter().map(|(name, _)| name.to_string()).collect(),
+            rows,
+            interner,
+            ColumnTypes::declared(COLUMNS.iter().map(|(_, t)| *t).collect()),
+        )
+    }
+
+    fn row(&self, node: usize) -> [String; 9] {
+        let n = &self.nodes[node];
+        let marker = if !self.can_expand(node) {
+            "  "
+        } else if n.expanded {
+            "▾ "
+        } else {
+            "▸ "
+        };
+        let executed = n.loops.is_some_and(|loops| loops > 0.0);
+        let (time, self_time) = match n.total_time() {
+            Some(_) if !executed => ("never executed".to_string(), String::new()),
+            Some(total) => (
+                format!("{:.3}", total),
+                format!("{:.3}", self.self_cost[node]),
+            ),
+            None => (String::new(), String::new()),
+        };
+        let buffers = match (n.shared_hit_blocks, n.shared_read_blocks) {
+            (0, 0) => String::new(),
+            (hit, 0) => format!("hit {}", hit),
+            (0, read) => format!("read {}", read),
+            (hit, read) => format!("hit {}, read {}", hit, read),
+        };
+        [
+            format!("{}{}{}", "  ".repeat(n.depth), marker, n.label),
+            format!("{:.2}", n.total_cost),
+            time,
+            self_time,
+            format!("{:.0}", n.plan_rows),
+            n.actual_rows
+                .filter(|_| executed)
+                .map(|rows| format!("{:.0}", rows))
+                .unwrap_or_default(),
+            n.loops
+                .map(|loops| format!("{:.0}", loops))
+                .unwrap_or_default(),
+            if executed {
+                misestimate(n.plan_rows, n.actual_rows.unwrap_or(0.0))
+            } else {
+                String::new()
+            },
+            buffers,
+        ]
+    }
+}
+
+/// Add a node and its children (depth first), returning its index.
+fn add_node(
+    nodes: &mut Vec<PlanNode>,
+    json: &Map<String, Value>,
+    parent: Option<usize>,
+    depth: usize,
+) -> usize {
+    let number = |key: &str| json.get(key).and_then(Value::as_f64);
+    let idx = nodes.len();
+    nodes.push(PlanNode {
+        label: node_label(json),
+        depth,
+        parent,
+        children: Vec::new(),
+        total_cost: number("Total Cost").unwrap_or(0.0),
+        plan_rows: number("Plan Rows").unwrap_or(0.0),
+        actual_time: number("Actual Total Time"),
+        actual_rows: number("Actual Rows"),
+        loops: number("Actual Loops"),
+        shared_hit_blocks: json
+            .get("Shared Hit Blocks")
+            .and_then(Value::as_u64)
+            .unwrap_or(0),
+        shared_read_blocks: json
+            .get("Shared Read Blocks")
+            .and_then(Value::as_u64)
+            .unwrap_or(0),
+        expanded: true,
+    });
+    for child in json
+        .get("Plans")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object)
+    {
+        let child_idx = add_node(nodes, child, Some(idx), depth + 1);
+        nodes[idx].children.push(child_idx);
+    }
+    idx
+}
+
+/// Describe a node the way psql's text plans do.
+fn node_label(json: &Map<String, Value>) -> String {
+    let text = |key: &str| json.get(key).and_then(Value::as_str);
+    let mut label = String::new();
+    if let Some(subplan) = text("Subplan Name") {
+        label.push_str(subplan);
+        label.push_str(": ");
+    }
+    if json.get("Parallel Aware").and_then(Value::as_bool) == Some(true) {
+        label.push_str("Parallel ");
+    }
+    // psql names ModifyTable nodes after their operation ("Update on t")
+    let node_type = match text("Node Type").unwrap_or("?") {
+        "ModifyTable" => text("Operation").unwrap_or("ModifyTable"),
+        node_type => node_type,
+    };
+    match text("Join Type").filter(|join| *join != "Inner") {
+        Some(join) if node_type.ends_with(" Join") => {
+            label.push_str(&node_type.replace(" Join", &format!(" {} Join", join)))
+        }
+        Some(join) => label.push_str(&format!("{} {} Join", node_type, join)),
+        None => label.push_str(node_type),
+    }
+    if let Some(index) = text("Index Name") {
+        label.push_str(&format!(" using {}", index));
+    }
+    if let Some(relation) = text("Relation Name").or(text("CTE Name")) {
+        label.push_str(&format!(" on {}", relation));
+        if let Some(alias) = text("Alias").filter(|alias| *alias != relation) {
+            label.push_str(&format!(" {}", alias));
+        }
+    }
+    label
+}
+
+/// Time (or cost, without timings) of each node excluding its children.
+fn self_costs(nodes: &[PlanNode]) -> Vec<f64> {
+    let timed = nodes[0].total_time().is_some();
+    let cost = |node: &PlanNode| {
+        if timed {
+            node.total_time().unwrap_or(0.0)
+        } else {
+            node.total_cost
+        }
+    };
+    nodes
+        .iter()
+        .map(|node| {
+            let children: f64 = node.children.iter().map(|c| cost(&nodes[*c])).sum();
+            (cost(node) - children).max(0.0)
+        })
+        .collect()
+}
+
+/// The nodes with the largest self cost, biggest first.
+fn expensive_nodes(self_cost: &[f64]) -> Vec<usize> {
+    let total: f64 = self_cost.iter().sum();
+    let mut nodes: Vec<usize> = (0..self_cost.len())
+        .filter(|node| total > 0.0 && self_cost[*node] >= total * EXPENSIVE_SHARE)
+        .collect();
+    nodes.sort_by(|a, b| self_cost[*b].total_cmp(&self_cost[*a]));
+    nodes.truncate(MAX_EXPENSIVE);
+    nodes
+}
+
+/// How far the row estimate is off, e.g. "40x under", if it is off much.
+fn misestimate(estimated: f64, actual: f64) -> String {
+    // A node returning no rows is estimated as 1
+    let (estimated, actual) = (estimated.max(1.0), actual.max(1.0));
+    if estimated / actual >= MISESTIMATE_FACTOR {
+        format!("{:.0}x over", estimated / actual)
+    } else if actual / estimated >= MISESTIMATE_FACTOR {
+        format!("{:.0}x under", actual / estimated)
+    } else {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANALYZED: &str = r#"[{
+        "Plan": {
+            "Node Type": "Hash Join", "Join Type": "Left", "Total Cost": 120.5,
+            "Plan Rows": 10, "Actual Total Time": 9.0, "Actual Rows": 400,
+            "Actual Loops": 1, "Shared Hit Blocks": 12, "Shared Read Blocks": 3,
+            "Plans": [
+                {"Node Type": "Seq Scan", "Parallel Aware": false,
+                 "Relation Name": "orders", "Alias": "o", "Total Cost": 80.0,
+                 "Plan Rows": 400, "Actual Total Time": 6.0, "Actual Rows": 400,
+                 "Actual Loops": 1, "Shared Hit Blocks": 10, "Shared Read Blocks": 3},
+                {"Node Type": "Hash", "Total Cost": 20.0, "Plan Rows": 50,
+                 "Actual Total Time": 1.0, "Actual Rows": 50, "Actual Loops": 1,
+                 "Plans": [
+                    {"Node Type": "Index Scan", "Index Name": "users_pkey",
+                     "Relation Name": "users", "Alias": "users", "Total Cost": 18.0,
+                     "Plan Rows": 50, "Actual Total Time": 0.8, "Actual Rows": 50,
+                     "Actual Loops": 1}
+                 ]},
+                {"Node Type": "Seq Scan", "Subplan Name": "SubPlan 1",
+                 "Relation Name": "audit", "Alias": "audit", "Total Cost": 1.0,
+                 "Plan Rows": 1, "Actual Total Time": 0.0, "Actual Rows": 0,
+                 "Actual Loops": 0}
+            ]
+        },
+        "Planning Time": 0.25,
+        "Execution Time": 9.5
+    }]"#;
+
+    fn names(plan: &Plan) -> Vec<String> {
+        let data = plan.table_data();
+        data.rows
+            .iter()
+            .map(|row| data.resolve(&row[0]).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_rows() {
+        let plan = Plan::from_json(ANALYZED).unwrap();
+        assert_eq!(
+            names(&plan),
+            vec![
+                "▾ Hash Left Join",
+                "    Seq Scan on orders o",
+                "  ▾ Hash",
+                "      Index Scan using users_pkey on users",
+                "    SubPlan 1: Seq Scan on audit",
+            ]
+        );
+        let data = plan.table_data();
+        assert_eq!(
+            data.resolve_row(&data.rows[0]),
+            vec![
+                "▾ Hash Left Join",
+                "120.50",
+                "9.000",
+                "2.000",
+                "10",
+                "400",
+                "1",
+                "40x under",
+                "hit 12, read 3"
+            ]
+        );
+        assert_eq!(
+            data.resolve_row(&data.rows[4])[2..7],
+            ["never executed", "", "1", "", "0"]
+        );
+        assert_eq!(plan.summary(), "Planning 0.25 ms, execution 9.50 ms");
+    }
+
+    #[test]
+    fn test_expensive_nodes() {
+        let plan = Plan::from_json(ANALYZED).unwrap();
+        // Seq Scan 6 ms, Hash Join 2 ms on its own; Hash and Index Scan are under 10%
+        assert_eq!(plan.expensive_lines(), vec![1, 0]);
+
+        // Without ANALYZE, the cost decides
+        let estimated = r#"[{"Plan": {"Node Type": "Sort", "Total Cost": 100.0, "Plan Rows": 5,
+            "Plans": [{"Node Type": "Seq Scan", "Relation Name": "t", "Alias": "t",
+                       "Total Cost": 10.0, "Plan Rows": 5}]}}]"#;
+        let plan = Plan::from_json(estimated).unwrap();
+        assert_eq!(plan.expensive_lines(), vec![0, 1]);
+        assert_eq!(plan.summary(), "Estimated cost 100.00");
+        let data = plan.table_data();
+        assert_eq!(
+            data.resolve_row(&data.rows[1])[2..8],
+            ["", "", "5", "", "", ""]
+        );
+    }
+
+    #[test]
+    fn test_collapse_node() {
+        let mut plan = Plan::from_json(ANALYZED).unwrap();
+        let hash = plan.node(2).unwrap();
+        plan.set_expanded(hash, false);
+        assert_eq!(names(&plan)[2], "  ▸ Hash");
+        assert_eq!(plan.node(3), Some(4));
+        assert_eq!(plan.nodes[plan.node(3).unwrap()].parent, Some(0));
+
+        // Leaves don't collapse
+        plan.set_expanded(1, false);
+        assert!(!plan.is_expanded(1));
+        assert_eq!(plan.table_data().rows.len(), 4);
+
+        plan.set_expanded(0, false);
+        assert_eq!(names(&plan), vec!["▸ Hash Left Join"]);
+        assert_eq!(plan.expensive_lines(), vec![0]);
+    }
+
+    #[test]
+    fn test_invalid_plan() {
+        assert!(Plan::from_json("not json").is_err());
+        assert!(Plan::from_json(r#"[{"Query": 1}]"#).is_err());
+    }
+}
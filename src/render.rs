@@ -4,13 +4,14 @@
 //! and rendering table panes with scroll indicators.
 
 use crate::column::ColumnConfig;
-use crate::parser::TableData;
+use crate::parser::{TableData, NULL_DISPLAY};
 use crate::state::{AppMode, PaneRenderData};
 use crate::workspace::{Tab, ViewMode, Workspace};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
 };
+use std::borrow::Cow;
 use std::cell::Cell as StdCell;
 
 /// Calculate auto-sized column widths from table data (raw values, no overrides).
@@ -20,15 +21,15 @@ pub fn calculate_auto_widths(data: &TableData) -> Vec<u16> {
     let mut widths = vec![0usize; num_cols];
 
     // Check header widths
-    for (i, header) in data.headers.iter().enumerate() {
-        widths[i] = widths[i].max(header.len());
+    for (i, width) in widths.iter_mut().enumerate() {
+        *width = header_len(data, i);
     }
 
     // Check data row widths
     for row in &data.rows {
         for (i, cell) in row.iter().enumerate() {
             if i < num_cols {
-                widths[i] = widths[i].max(data.resolve(cell).len());
+                widths[i] = widths[i].max(cell_len(data.value(cell)));
             }
         }
     }
@@ -37,6 +38,58 @@ pub fn calculate_auto_widths(data: &TableData) -> Vec<u16> {
     widths.iter().map(|w| (*w + 1) as u16).collect()
 }
 
+/// Check if the header shows column types under the names.
+///
+/// Done for database results without rows, where the types are all there is to see.
+pub fn shows_column_types(data: &TableData) -> bool {
+    data.rows.is_empty() && data.column_types.is_declared()
+}
+
+/// Length of a column header for column sizing, including its type if shown.
+pub fn header_len(data: &TableData, col: usize) -> usize {
+    let name_len = data.headers.get(col).map_or(0, |header| header.len());
+    if shows_column_types(data) {
+        name_len.max(data.column_type_name(col).len())
+    } else {
+        name_len
+    }
+}
+
+/// Length of a cell value for column sizing (NULL is sized as its display text).
+pub fn cell_len(value: Option<&str>) -> usize {
+    value.map_or(NULL_DISPLAY.len(), str::len)
+}
+
+/// Text shown for a cell in the table view.
+/// Newlines are shown as `↵` so multi-line values keep each row one line high,
+/// and NULL (`None`) is shown as `NULL`.
+pub fn display_cell(value: Option<&str>) -> Cow<'_, str> {
+    let Some(value) = value else {
+        return Cow::Borrowed(NULL_DISPLAY);
+    };
+    if value.contains(['\n', '\r']) {
+        Cow::Owned(
+            value
+                .replace("\r\n", "\n")
+                .chars()
+                .map(|c| if c == '\n' || c == '\r' { '↵' } else { c })
+                .collect(),
+        )
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Line for a cell, right-aligned for numeric columns.
+fn aligned<'a>(text: impl Into<Cow<'a, str>>, right: bool) -> Line<'a> {
+    let line = Line::from(text.into());
+    if right {
+        line.alignment(Alignment::Right)
+    } else {
+        line
+    }
+}
+
 /// Calculate column widths from table data.
 /// Returns a Constraint for each column sized to fit the maximum content width.
 /// If a ColumnConfig is provided, uses width overrides where set.
@@ -91,60 +144,78 @@ pub fn build_pane_render_data(tab: &Tab, viewport_height: usize) -> PaneRenderDa
     let buffer = viewport_height.saturating_mul(2);
 
     // Calculate filtered rows with viewport windowing
-    let (display_rows, displayed_row_count, viewport_row_offset) = if tab.filter_text.is_empty() {
-        let total = tab.data.rows.len();
-        let start = selected.saturating_sub(buffer);
-        let end = selected.saturating_add(buffer).min(total);
-        // Resolve symbols to strings for PaneRenderData
-        let rows: Vec<Vec<String>> = tab.data.rows[start..end]
-            .iter()
-            .map(|row| {
-                row.iter()
-                    .map(|s| tab.data.resolve(s).to_string())
-                    .collect()
-            })
-            .collect();
-        (rows, total, start)
-    } else {
-        let filter_lower = tab.filter_text.to_lowercase();
-        // Collect matching indices (scan only, no clone)
-        let filtered_indices: Vec<usize> = tab
-            .data
-            .rows
-            .iter()
-            .enumerate()
-            .filter(|(_, row)| {
-                row.iter().any(|cell| {
-                    tab.data
-                        .resolve(cell)
-                        .to_lowercase()
-                        .contains(&filter_lower)
+    let row_nulls = |row: &[lasso::Spur]| row.iter().map(|s| tab.data.is_null(s)).collect();
+    let (display_rows, null_cells, highlighted, displayed_row_count, viewport_row_offset) =
+        if tab.filter_text.is_empty() {
+            let total = tab.data.rows.len();
+            let start = selected.saturating_sub(buffer);
+            let end = selected.saturating_add(buffer).min(total);
+            // Resolve symbols to strings for PaneRenderData
+            let rows: Vec<Vec<String>> = tab.data.rows[start..end]
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|s| tab.data.resolve(s).to_string())
+                        .collect()
                 })
-            })
-            .map(|(i, _)| i)
-            .collect();
-        let total = filtered_indices.len();
-        let start = selected.saturating_sub(buffer).min(total);
-        let end = selected.saturating_add(buffer).min(total);
-        // Ensure start <= end (handles case where selected > total)
-        let start = start.min(end);
-        let rows: Vec<Vec<String>> = filtered_indices[start..end]
-            .iter()
-            .map(|&i| {
-                tab.data.rows[i]
-                    .iter()
-                    .map(|s| tab.data.resolve(s).to_string())
-                    .collect()
-            })
-            .collect();
-        (rows, total, start)
-    };
+                .collect();
+            let nulls = tab.data.rows[start..end]
+                .iter()
+                .map(|row| row_nulls(row))
+                .collect();
+            let highlighted = (start..end)
+                .map(|i| tab.highlighted_rows.contains(&i))
+                .collect();
+            (rows, nulls, highlighted, total, start)
+        } else {
+            let filter_lower = tab.filter_text.to_lowercase();
+            // Collect matching indices (scan only, no clone)
+            let filtered_indices: Vec<usize> = tab
+                .data
+                .rows
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| {
+                    row.iter().any(|cell| {
+                        tab.data
+                            .resolve(cell)
+                            .to_lowercase()
+                            .contains(&filter_lower)
+                    })
+                })
+                .map(|(i, _)| i)
+                .collect();
+            let total = filtered_indices.len();
+            let start = selected.saturating_sub(buffer).min(total);
+            let end = selected.saturating_add(buffer).min(total);
+            // Ensure start <= end (handles case where selected > total)
+            let start = start.min(end);
+            let rows: Vec<Vec<String>> = filtered_indices[start..end]
+                .iter()
+                .map(|&i| {
+                    tab.data.rows[i]
+                        .iter()
+                        .map(|s| tab.data.resolve(s).to_string())
+                        .collect()
+                })
+                .collect();
+            let nulls = filtered_indices[start..end]
+                .iter()
+                .map(|&i| row_nulls(&tab.data.rows[i]))
+                .collect();
+            let highlighted = filtered_indices[start..end]
+                .iter()
+                .map(|i| tab.highlighted_rows.contains(i))
+                .collect();
+            (rows, nulls, highlighted, total, start)
+        };
 
     PaneRenderData {
         name: tab.name.clone(),
         total_rows: tab.data.rows.len(),
         displayed_row_count,
         display_rows,
+        null_cells,
         headers: tab.data.headers.clone(),
         visible_cols,
         widths,
@@ -155,6 +226,17 @@ pub fn build_pane_render_data(tab: &Tab, viewport_height: usize) -> PaneRenderDa
         hidden_count,
         selected_row: tab.table_state.selected(),
         viewport_row_offset,
+        issue_count: tab.issues.len(),
+        more_available: tab.more_available,
+        column_types: (0..tab.data.headers.len())
+            .map(|col| tab.data.column_type(col))
+            .collect(),
+        header_types: shows_column_types(&tab.data).then(|| {
+            (0..tab.data.headers.len())
+                .map(|col| tab.data.column_type_name(col).to_string())
+                .collect()
+        }),
+        highlighted,
     }
 }
 
@@ -336,6 +418,21 @@ pub fn render_table_pane(
     // Style for indicator cells
     let indicator_style = Style::default().bg(Color::DarkGray).fg(Color::Gray);
 
+    // NULL cells are dimmed so they stand apart from the text "NULL"
+    let null_style = Style::default()
+        .fg(Color::DarkGray)
+        .add_modifier(Modifier::ITALIC);
+
+    // Highlighted rows (e.g. expensive plan nodes) stand out in red
+    let highlight_style = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
+
+    // Numbers are right-aligned so digits line up
+    let is_numeric = |col: usize| {
+        pane.column_types
+            .get(col)
+            .is_some_and(|column_type| column_type.is_numeric())
+    };
+
     // Create header row with bold style (only columns in scroll window)
     // Prepend/append indicator cells if needed
     let mut header_cells: Vec<Cell> = Vec::new();
@@ -343,33 +440,59 @@ pub fn render_table_pane(
         header_cells.push(Cell::from(" ").style(indicator_style));
     }
     for &i in &render_cols {
-        header_cells.push(
-            Cell::from(pane.headers[i].as_str())
-                .style(Style::default().add_modifier(Modifier::BOLD)),
-        );
+        let mut header = Text::from(aligned(pane.headers[i].as_str(), is_numeric(i)));
+        if let Some(type_name) = pane.header_types.as_ref().and_then(|types| types.get(i)) {
+            header.push_line(
+                aligned(type_name.as_str(), is_numeric(i)).style(
+                    Style::default()
+                        .fg(Color::DarkGray)
+                        .remove_modifier(Modifier::BOLD),
+                ),
+            );
+        }
+        header_cells.push(Cell::from(header).style(Style::default().add_modifier(Modifier::BOLD)));
     }
     if has_right_overflow {
         header_cells.push(Cell::from(" ").style(indicator_style));
     }
-    let header_row = Row::new(header_cells).style(Style::default().fg(Color::Yellow));
+    let header_height = if pane.header_types.is_some() { 2 } else { 1 };
+    let header_row = Row::new(header_cells)
+        .height(header_height)
+        .style(Style::default().fg(Color::Yellow));
 
     // Create data rows from filtered set (only columns in scroll window)
     // Prepend/append indicator cells if needed
     let data_rows: Vec<Row> = pane
         .display_rows
         .iter()
-        .map(|row| {
+        .zip(&pane.null_cells)
+        .zip(&pane.highlighted)
+        .map(|((row, nulls), &highlighted)| {
             let mut cells: Vec<Cell> = Vec::new();
             if has_left_overflow {
                 cells.push(Cell::from("◀").style(indicator_style));
             }
             for &i in &render_cols {
-                cells.push(Cell::from(row.get(i).map(|s| s.as_str()).unwrap_or("")));
+                let is_null = nulls.get(i).copied().unwrap_or(false);
+                let value = row.get(i).map(|s| s.as_str()).unwrap_or("");
+                let cell = Cell::from(aligned(
+                    display_cell((!is_null).then_some(value)),
+                    is_numeric(i),
+                ));
+                cells.push(if is_null {
+                    cell.style(null_style)
+                } else {
+                    cell
+                });
             }
             if has_right_overflow {
                 cells.push(Cell::from("▶").style(indicator_style));
             }
-            Row::new(cells)
+            if highlighted {
+                Row::new(cells).style(highlight_style)
+            } else {
+                Row::new(cells)
+            }
         })
         .collect();
 
@@ -476,14 +599,48 @@ pub fn build_pane_title(
         String::new()
     };
 
+    let more_info = if pane.more_available {
+        format!(" {}", more_rows_info(pane.total_rows))
+    } else {
+        String::new()
+    };
+
+    let issues = if pane.issue_count > 0 {
+        format!(" {}", issue_info(pane.issue_count))
+    } else {
+        String::new()
+    };
+
     let focus_indicator = if is_focused { "*" } else { "" };
 
     format!(
-        "{}{} {}{}",
-        focus_indicator, pane.name, position, filter_info
+        "{}{} {}{}{}{}",
+        focus_indicator, pane.name, position, more_info, filter_info, issues
     )
 }
 
+/// Frames of the spinner shown while a query runs
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Spinner character for the time a task has been running (10 frames per second).
+pub fn spinner_frame(elapsed: std::time::Duration) -> char {
+    SPINNER_FRAMES[(elapsed.as_millis() / 100) as usize % SPINNER_FRAMES.len()]
+}
+
+/// Shown in pane titles while a database cursor has more rows to fetch.
+pub fn more_rows_info(loaded_rows: usize) -> String {
+    format!("{} loaded / more available", loaded_rows)
+}
+
+/// Warning shown in pane titles when input lines were malformed.
+pub fn issue_info(issue_count: usize) -> String {
+    if issue_count == 1 {
+        "⚠ 1 malformed line (I: view)".to_string()
+    } else {
+        format!("⚠ {} malformed lines (I: view)", issue_count)
+    }
+}
+
 /// Build tab bar string for multi-tab display.
 /// Format: "1:name 2:name [3:active] 4:name | " with numbers matching keyboard shortcuts.
 pub fn build_tab_bar(workspace: &Workspace) -> String {
@@ -534,11 +691,11 @@ pub fn build_controls_hint(view_mode: ViewMode, is_split: bool, tab_count: usize
 
     match view_mode {
         ViewMode::TableList => format!(
-            "{}{}Enter: select, /: filter, q: quit",
+            "{}{}Enter: open, D: describe, h/l: collapse/expand, /: filter, q: quit",
             split_controls, tab_controls
         ),
         ViewMode::TableData => format!(
-            "{}{}+/-: width, H/S: hide/show, </>: move, E: export, 0: reset, Esc: back, q: quit",
+            "{}{}D: describe, f/r: follow key/referrers, X: explain, +/-: width, H/S: hide/show, </>: move, E: export, 0: reset, Esc: back, q: quit",
             split_controls, tab_controls
         ),
         ViewMode::PipeData => format!(
@@ -549,7 +706,16 @@ pub fn build_controls_hint(view_mode: ViewMode, is_split: bool, tab_count: usize
 }
 
 /// Render input bar for query/search/export modes.
-pub fn render_input_bar(frame: &mut Frame, area: Rect, mode: AppMode, input_buffer: &str) {
+///
+/// `detail` is shown on the right of the border, e.g. the outcome of a
+/// query recalled from history.
+pub fn render_input_bar(
+    frame: &mut Frame,
+    area: Rect,
+    mode: AppMode,
+    input_buffer: &str,
+    detail: Option<&str>,
+) {
     let (prefix, style) = match mode {
         AppMode::QueryInput => (":", Style::default().fg(Color::Cyan)),
         AppMode::SearchInput => ("/", Style::default().fg(Color::Yellow)),
@@ -558,13 +724,41 @@ pub fn render_input_bar(frame: &mut Frame, area: Rect, mode: AppMode, input_buff
     };
 
     let input_text = format!("{}{}", prefix, input_buffer);
-    let input_widget = Paragraph::new(input_text)
-        .style(style)
-        .block(Block::default().borders(Borders::ALL));
+    let mut block = Block::default().borders(Borders::ALL);
+    if let Some(detail) = detail {
+        block = block.title(Line::from(format!(" {} ", detail)).right_aligned());
+    }
+    let input_widget = Paragraph::new(input_text).style(style).block(block);
 
     frame.render_widget(input_widget, area);
 }
 
+/// Render the Ctrl+R query history search prompt, like a shell's.
+pub fn render_history_search(
+    frame: &mut Frame,
+    area: Rect,
+    term: &str,
+    matched: Option<&str>,
+    detail: Option<&str>,
+) {
+    let prompt_text = match matched {
+        Some(query) => format!("(reverse-i-search)`{}': {}", term, query),
+        None if term.is_empty() => "(reverse-i-search)`': ".to_string(),
+        None => format!("(failing reverse-i-search)`{}': ", term),
+    };
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Ctrl+R: older, Enter: run, Esc: edit, Ctrl+G: cancel ");
+    if let Some(detail) = detail {
+        block = block.title(Line::from(format!(" {} ", detail)).right_aligned());
+    }
+    let prompt_widget = Paragraph::new(prompt_text)
+        .style(Style::default().fg(Color::Cyan))
+        .block(block);
+
+    frame.render_widget(prompt_widget, area);
+}
+
 /// Render export format selection prompt.
 pub fn render_format_prompt(frame: &mut Frame, area: Rect) {
     let prompt_text = "Export format: [C]SV or [J]SON (Esc to cancel)";
@@ -0,0 +1,745 @@
+//! Schema browser tree for database connections.
+//!
+//! Schemas expand into groups of tables, views, materialized views, foreign
+//! tables and functions, and relations expand into their columns. The visible
+//! nodes are shown as the rows of a regular table, so filtering and export
+//! work on the tree like on any other tab.
+//!
+//! Also holds the foreign keys followed from a row to the rows it references.
+
+use lasso::Rodeo;
+
+use crate::parser::TableData;
+
+/// Headers of the table the tree is shown as.
+const HEADERS: [&str; 4] = ["Name", "Kind", "Rows (est.)", "Size"];
+
+/// Schema expanded when the browser opens.
+const DEFAULT_SCHEMA: &str = "public";
+
+/// Kind of object listed under a schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ObjectKind {
+    Table,
+    View,
+    MaterializedView,
+    ForeignTable,
+    Function,
+}
+
+impl ObjectKind {
+    /// All kinds, in the order their groups are shown.
+    pub const ALL: [ObjectKind; 5] = [
+        ObjectKind::Table,
+        ObjectKind::View,
+        ObjectKind::MaterializedView,
+        ObjectKind::ForeignTable,
+        ObjectKind::Function,
+    ];
+
+    /// Kind of a relation from its `pg_class.relkind`.
+    pub fn from_relkind(relkind: &str) -> Option<Self> {
+        match relkind {
+            "r" | "p" => Some(ObjectKind::Table),
+            "v" => Some(ObjectKind::View),
+            "m" => Some(ObjectKind::MaterializedView),
+            "f" => Some(ObjectKind::ForeignTable),
+            _ => None,
+        }
+    }
+
+    /// Name shown in the Kind column, e.g. "materialized view".
+    pub fn label(self) -> &'static str {
+        match self {
+            ObjectKind::Table => "table",
+            ObjectKind::View => "view",
+            ObjectKind::MaterializedView => "materialized view",
+            ObjectKind::ForeignTable => "foreign table",
+            ObjectKind::Function => "function",
+        }
+    }
+
+    fn group_label(self) -> &'static str {
+        match self {
+            ObjectKind::Table => "Tables",
+            ObjectKind::View => "Views",
+            ObjectKind::MaterializedView => "Materialized views",
+            ObjectKind::ForeignTable => "Foreign tables",
+            ObjectKind::Function => "Functions",
+        }
+    }
+
+    /// Check if objects of this kind have rows and columns to browse.
+    pub fn is_relation(self) -> bool {
+        self != ObjectKind::Function
+    }
+}
+
+/// A column of a relation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// Type as PostgreSQL formats it, e.g. "character varying(40)"
+    pub type_name: String,
+    pub not_null: bool,
+}
+
+/// A table, view or function in a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbObject {
+    /// Relation name, or function name with its argument types
+    pub name: String,
+    pub kind: ObjectKind,
+    /// Row count estimated from planner statistics (None if never analyzed)
+    pub row_estimate: Option<i64>,
+    /// Size on disk in bytes, including indexes and TOAST
+    pub size: Option<i64>,
+    /// Columns, once loaded
+    pub columns: Option<Vec<ColumnInfo>>,
+    expanded: bool,
+}
+
+impl DbObject {
+    pub fn new(name: String, kind: ObjectKind) -> Self {
+        Self {
+            name,
+            kind,
+            row_estimate: None,
+            size: None,
+            columns: None,
+            expanded: false,
+        }
+    }
+}
+
+/// A schema and the objects in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema {
+    pub name: String,
+    /// Objects sorted by kind, then name
+    pub objects: Vec<DbObject>,
+    expanded: bool,
+    expanded_groups: Vec<ObjectKind>,
+}
+
+impl Schema {
+    pub fn new(name: String, mut objects: Vec<DbObject>) -> Self {
+        objects.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+        Self {
+            name,
+            objects,
+            expanded: false,
+            expanded_groups: Vec::new(),
+        }
+    }
+
+    fn objects_of(&self, kind: ObjectKind) -> impl Iterator<Item = (usize, &DbObject)> {
+        self.objects
+            .iter()
+            .enumerate()
+            .filter(move |(_, obj)| obj.kind == kind)
+    }
+}
+
+/// A node of the tree, by position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeNode {
+    /// Schema index
+    Schema(usize),
+    /// Schema index and the kind of objects grouped
+    Group(usize, ObjectKind),
+    /// Schema and object index
+    Object(usize, usize),
+    /// Schema, object and column index
+    Column(usize, usize, usize),
+}
+
+/// Schemas → object groups → objects → columns, with expansion state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaTree {
+    pub schemas: Vec<Schema>,
+    /// Visible nodes, in display order
+    lines: Vec<TreeNode>,
+}
+
+impl SchemaTree {
+    /// Build a tree with the `public` schema and its tables expanded.
+    pub fn new(schemas: Vec<Schema>) -> Self {
+        let mut tree = Self {
+            schemas,
+            lines: Vec::new(),
+        };
+        if let Some(schema) = tree.schemas.iter_mut().find(|s| s.name == DEFAULT_SCHEMA) {
+            schema.expanded = true;
+            schema.expanded_groups.push(ObjectKind::Table);
+        }
+        tree.update_lines();
+        tree
+    }
+
+    /// Visible nodes, one per row of `table_data`.
+    pub fn lines(&self) -> &[TreeNode] {
+        &self.lines
+    }
+
+    /// Node shown on the given row.
+    pub fn node(&self, line: usize) -> Option<TreeNode> {
+        self.lines.get(line).copied()
+    }
+
+    /// Row the given node is shown on, if visible.
+    pub fn line_of(&self, node: TreeNode) -> Option<usize> {
+        self.lines.iter().position(|n| *n == node)
+    }
+
+    /// Schema name and object of an object node.
+    pub fn object(&self, node: TreeNode) -> Option<(&str, &DbObject)> {
+        match node {
+            TreeNode::Object(s, o) | TreeNode::Column(s, o, _) => {
+                let schema = self.schemas.get(s)?;
+                Some((schema.name.as_str(), schema.objects.get(o)?))
+            }
+            _ => None,
+        }
+    }
+
+    /// Check if a node is expanded.
+    pub fn is_expanded(&self, node: TreeNode) -> bool {
+        match node {
+            TreeNode::Schema(s) => self.schemas[s].expanded,
+            TreeNode::Group(s, kind) => self.schemas[s].expanded_groups.contains(&kind),
+            TreeNode::Object(s, o) => self.schemas[s].objects[o].expanded,
+            TreeNode::Column(..) => false,
+        }
+    }
+
+    /// Check if a node has children (relations need their columns loaded).
+    pub fn can_expand(&self, node: TreeNode) -> bool {
+        match node {
+            TreeNode::Schema(_) | TreeNode::Group(..) => true,
+            TreeNode::Object(s, o) => self.schemas[s].objects[o].kind.is_relation(),
+            TreeNode::Column(..) => false,
+        }
+    }
+
+    /// Check if a relation node still needs its columns before expanding.
+    pub fn needs_columns(&self, node: TreeNode) -> bool {
+        matches!(node, TreeNode::Object(s, o)
+            if self.schemas[s].objects[o].kind.is_relation()
+                && self.schemas[s].objects[o].columns.is_none())
+    }
+
+    /// Store the loaded columns of a relation.
+    pub fn set_columns(&mut self, node: TreeNode, columns: Vec<ColumnInfo>) {
+        if let TreeNode::Object(s, o) = node {
+            self.schemas[s].objects[o].columns = Some(columns);
+        }
+    }
+
+    /// Expand or collapse a node. Does nothing for leaves.
+    pub fn set_expanded(&mut self, node: TreeNode, expanded: bool) {
+        if !self.can_expand(node) {
+            return;
+        }
+        match node {
+            TreeNode::Schema(s) => self.schemas[s].expanded = expanded,
+            TreeNode::Group(s, kind) => {
+                let groups = &mut self.schemas[s].expanded_groups;
+                groups.retain(|k| *k != kind);
+                if expanded {
+                    groups.push(kind);
+                }
+            }
+            TreeNode::Object(s, o) => self.schemas[s].objects[o].expanded = expanded,
+            TreeNode::Column(..) => {}
+        }
+        self.update_lines();
+    }
+
+    /// The node containing this one.
+    pub fn parent(&self, node: TreeNode) -> Option<TreeNode> {
+        match node {
+            TreeNode::Schema(_) => None,
+            TreeNode::Group(s, _) => Some(TreeNode::Schema(s)),
+            TreeNode::Object(s, o) => Some(TreeNode::Group(s, self.schemas[s].objects[o].kind)),
+            TreeNode::Column(s, o, _) => Some(TreeNode::Object(s, o)),
+        }
+    }
+
+    fn update_lines(&mut self) {
+        self.lines.clear();
+        for (s, schema) in self.schemas.iter().enumerate() {
+            self.lines.push(TreeNode::Schema(s));
+            if !schema.expanded {
+                continue;
+            }
+            for kind in ObjectKind::ALL {
+                if schema.objects_of(kind).next().is_none() {
+                    continue;
+                }
+                self.lines.push(TreeNode::Group(s, kind));
+                if !schema.expanded_groups.contains(&kind) {
+                    continue;
+                }
+                for (o, object) in schema.objects_of(kind) {
+                    self.lines.push(TreeNode::Object(s, o));
+                    if let (true, Some(columns)) = (object.expanded, &object.columns) {
+                        self.lines
+                            .extend((0..columns.len()).map(|c| TreeNode::Column(s, o, c)));
+                    }
+                }
+            }
+        }
+    }
+
+    /// The visible nodes as a table: Name (indented by depth), Kind, Rows, Size.
+    pub fn table_data(&self) -> TableData {
+        let mut interner = Rodeo::default();
+        let rows = self
+            .lines
+            .iter()
+            .map(|node| {
+                self.row(*node)
+                    .iter()
+                    .map(|cell| interner.get_or_intern(cell))
+                    .collect()
+            })
+            .collect();
+        TableData::new(
+            HEADERS.iter().map(|h| h.to_string()).collect(),
+            rows,
+            interner,
+        )
+    }
+
+    fn row(&self, node: TreeNode) -> [String; 4] {
+        let marker = if !self.can_expand(node) {
+            "  "
+        } else if self.is_expanded(node) {
+            "▾ "
+        } else {
+            "▸ "
+        };
+        match node {
+            TreeNode::Schema(s) => {
+                let schema = &self.schemas[s];
+                let size: i64 = schema.objects.iter().filter_map(|o| o.size).sum();
+                [
+                    format!("{}{}", marker, schema.name),
+                    "schema".to_string(),
+                    String::new(),
+                    if size > 0 {
+                        format_size(size)
+                    } else {
+                        String::new()
+                    },
+                ]
+            }
+            TreeNode::Group(s, kind) => [
+                format!(
+                    "  {}{} ({})",
+                    marker,
+                    kind.group_label(),
+                    self.schemas[s].objects_of(kind).count()
+                ),
+                String::new(),
+                String::new(),
+                String::new(),
+            ],
+            TreeNode::Object(s, o) => {
+                let object = &self.schemas[s].objects[o];
+                [
+                    format!("    {}{}", marker, object.name),
+                    object.kind.label().to_string(),
+                    object
+                        .row_estimate
+                        .map(|n| n.to_string())
+                        .unwrap_or_default(),
+                    object.size.map(format_size).unwrap_or_default(),
+                ]
+            }
+            TreeNode::Column(s, o, c) => {
+                let column = &self.schemas[s].objects[o].columns.as_ref().unwrap()[c];
+                let type_name = if column.not_null {
+                    format!("{} not null", column.type_name)
+                } else {
+                    column.type_name.clone()
+                };
+                [
+                    format!("      {}{}", marker, column.name),
+                    type_name,
+                    String::new(),
+                    String::new(),
+                ]
+            }
+        }
+    }
+}
+
+/// A foreign key from the columns of one relation to those of another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKey {
+    pub name: String,
+    /// Referencing (child) relation
+    pub schema: String,
+    pub table: String,
+    pub columns: Vec<String>,
+    /// Referenced (parent) relation, with columns in the same order
+    pub ref_schema: String,
+    pub ref_table: String,
+    pub ref_columns: Vec<String>,
+}
+
+/// Rows to look up in a relation: those whose columns equal the given values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowLookup {
+    pub schema: String,
+    pub table: String,
+    /// Column name and value pairs
+    pub conditions: Vec<(String, String)>,
+}
+
+impl ForeignKey {
+    /// Check if the key goes from this relation.
+    pub fn is_from(&self, schema: &str, table: &str) -> bool {
+        self.schema == schema && self.table == table
+    }
+
+    /// Check if the key points at this relation.
+    pub fn is_to(&self, schema: &str, table: &str) -> bool {
+        self.ref_schema == schema && self.ref_table == table
+    }
+
+    /// Rows at the other end of the key from a row given as (column, value) pairs.
+    ///
+    /// Goes from a referencing row to the referenced row, or with `reverse`
+    /// from a referenced row to the rows referencing it. None if a key column
+    /// is missing or NULL, as NULL references nothing.
+    pub fn follow(&self, row: &[(String, Option<String>)], reverse: bool) -> Option<RowLookup> {
+        let (from, to, schema, table) = if reverse {
+            (&self.ref_columns, &self.columns, &self.schema, &self.table)
+        } else {
+            (
+                &self.columns,
+                &self.ref_columns,
+                &self.ref_schema,
+                &self.ref_table,
+            )
+        };
+        let conditions = from
+            .iter()
+            .zip(to)
+            .map(|(from, to)| {
+                let (_, value) = row.iter().find(|(column, _)| column == from)?;
+                Some((to.clone(), value.clone()?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(RowLookup {
+            schema: schema.clone(),
+            table: table.clone(),
+            conditions,
+        })
+    }
+}
+
+/// Rows to open when following foreign keys from a column of a row.
+///
+/// Forward, every key of `schema.table` that includes `column` leads to its
+/// referenced row. In `reverse`, every key referencing `schema.table` leads to
+/// its referencing rows; keys on `column` are preferred if there are any.
+/// Returns a message to show when there is nothing to follow.
+pub fn follow_foreign_keys(
+    keys: &[ForeignKey],
+    (schema, table): (&str, &str),
+    column: &str,
+    row: &[(String, Option<String>)],
+    reverse: bool,
+) -> Result<Vec<RowLookup>, String> {
+    let candidates: Vec<&ForeignKey> = if reverse {
+        let incoming: Vec<&ForeignKey> = keys.iter().filter(|k| k.is_to(schema, table)).collect();
+        if incoming.is_empty() {
+            return Err(format!("No foreign keys reference {}", table));
+        }
+        let on_column: Vec<&ForeignKey> = incoming
+            .iter()
+            .copied()
+            .filter(|k| k.ref_columns.iter().any(|c| c == column))
+            .collect();
+        if on_column.is_empty() {
+            incoming
+        } else {
+            on_column
+        }
+    } else {
+        let outgoing: Vec<&ForeignKey> = keys
+            .iter()
+            .filter(|k| k.is_from(schema, table) && k.columns.iter().any(|c| c == column))
+            .collect();
+        if outgoing.is_empty() {
+            return Err(format!("{} is not part of a foreign key", column));
+        }
+        outgoing
+    };
+
+    let lookups: Vec<RowLookup> = candidates
+        .iter()
+        .filter_map(|k| k.follow(row, reverse))
+        .collect();
+    if lookups.is_empty() {
+        return Err("The key is NULL, so it references nothing".to_string());
+    }
+    Ok(lookups)
+}
+
+/// Format a size in bytes the way `pg_size_pretty` does, e.g. "16 kB".
+pub fn format_size(bytes: i64) -> String {
+    const UNITS: [&str; 6] = ["bytes", "kB", "MB", "GB", "TB", "PB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size.abs() >= 10.0 * 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{} {}", size.round() as i64, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(name: &str, kind: ObjectKind) -> DbObject {
+        DbObject::new(name.to_string(), kind)
+    }
+
+    fn sample_tree() -> SchemaTree {
+        let mut users = object("users", ObjectKind::Table);
+        users.row_estimate = Some(1200);
+        users.size = Some(16384);
+        SchemaTree::new(vec![
+            Schema::new(
+                "public".to_string(),
+                vec![
+                    object("active_users", ObjectKind::View),
+                    users,
+                    object("orders", ObjectKind::Table),
+                ],
+            ),
+            Schema::new(
+                "sales".to_string(),
+                vec![object("total(integer)", ObjectKind::Function)],
+            ),
+        ])
+    }
+
+    fn names(tree: &SchemaTree) -> Vec<String> {
+        let data = tree.table_data();
+        data.rows
+            .iter()
+            .map(|row| data.resolve(&row[0]).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_public_tables_start_expanded() {
+        let tree = sample_tree();
+        assert_eq!(
+            names(&tree),
+            vec![
+                "▾ public",
+                "  ▾ Tables (2)",
+                "    ▸ orders",
+                "    ▸ users",
+                "  ▸ Views (1)",
+                "▸ sales",
+            ]
+        );
+
+        let data = tree.table_data();
+        assert_eq!(
+            data.resolve_row(&data.rows[3]),
+            vec!["    ▸ users", "table", "1200", "16 kB"]
+        );
+        assert_eq!(data.resolve_row(&data.rows[0])[3], "16 kB");
+    }
+
+    #[test]
+    fn test_expand_and_collapse() {
+        let mut tree = sample_tree();
+        let sales = tree.node(5).unwrap();
+        assert_eq!(sales, TreeNode::Schema(1));
+        tree.set_expanded(sales, true);
+        tree.set_expanded(TreeNode::Group(1, ObjectKind::Function), true);
+        assert_eq!(
+            names(&tree)[5..],
+            ["▾ sales", "  ▾ Functions (1)", "      total(integer)"]
+        );
+        // Functions have no children
+        let function = tree.node(7).unwrap();
+        assert!(!tree.can_expand(function));
+        assert!(!tree.needs_columns(function));
+
+        tree.set_expanded(TreeNode::Schema(0), false);
+        assert_eq!(tree.lines().len(), 4);
+        assert_eq!(tree.line_of(TreeNode::Schema(1)), Some(1));
+        assert_eq!(
+            tree.parent(TreeNode::Group(1, ObjectKind::Function)),
+            Some(TreeNode::Schema(1))
+        );
+    }
+
+    #[test]
+    fn test_relation_columns() {
+        let mut tree = sample_tree();
+        let users = tree.node(3).unwrap();
+        assert_eq!(
+            tree.object(users).map(|(s, o)| (s, o.name.as_str())),
+            Some(("public", "users"))
+        );
+        assert!(tree.needs_columns(users));
+
+        tree.set_columns(
+            users,
+            vec![
+                ColumnInfo {
+                    name: "id".to_string(),
+                    type_name: "integer".to_string(),
+                    not_null: true,
+                },
+                ColumnInfo {
+                    name: "email".to_string(),
+                    type_name: "text".to_string(),
+                    not_null: false,
+                },
+            ],
+        );
+        tree.set_expanded(users, true);
+
+        let data = tree.table_data();
+        assert_eq!(
+            data.resolve_row(&data.rows[4]),
+            vec!["        id", "integer not null", "", ""]
+        );
+        assert_eq!(tree.parent(tree.node(5).unwrap()), Some(users));
+    }
+
+    #[test]
+    fn test_follow_foreign_key() {
+        let fk = ForeignKey {
+            name: "order_lines_order_fkey".to_string(),
+            schema: "public".to_string(),
+            table: "order_lines".to_string(),
+            columns: vec!["shop".to_string(), "order_no".to_string()],
+            ref_schema: "sales".to_string(),
+            ref_table: "orders".to_string(),
+            ref_columns: vec!["shop_id".to_string(), "no".to_string()],
+        };
+        let row = |shop: Option<&str>| {
+            vec![
+                ("line".to_string(), Some("1".to_string())),
+                ("order_no".to_string(), Some("42".to_string())),
+                ("shop".to_string(), shop.map(str::to_string)),
+            ]
+        };
+
+        assert_eq!(
+            fk.follow(&row(Some("7")), false),
+            Some(RowLookup {
+                schema: "sales".to_string(),
+                table: "orders".to_string(),
+                conditions: vec![
+                    ("shop_id".to_string(), "7".to_string()),
+                    ("no".to_string(), "42".to_string())
+                ],
+            })
+        );
+        // NULL references nothing
+        assert_eq!(fk.follow(&row(None), false), None);
+
+        let parent = vec![
+            ("no".to_string(), Some("42".to_string())),
+            ("shop_id".to_string(), Some("7".to_string())),
+        ];
+        let children = fk.follow(&parent, true).unwrap();
+        assert_eq!(
+            (children.schema.as_str(), children.table.as_str()),
+            ("public", "order_lines")
+        );
+        assert_eq!(
+            children.conditions,
+            vec![
+                ("shop".to_string(), "7".to_string()),
+                ("order_no".to_string(), "42".to_string())
+            ]
+        );
+        // The child row has no parent key columns
+        assert_eq!(fk.follow(&row(Some("7")), true), None);
+    }
+
+    #[test]
+    fn test_follow_foreign_keys_picks_keys() {
+        let key =
+            |name: &str, table: &str, column: &str, ref_table: &str, ref_column: &str| ForeignKey {
+                name: name.to_string(),
+                schema: "public".to_string(),
+                table: table.to_string(),
+                columns: vec![column.to_string()],
+                ref_schema: "public".to_string(),
+                ref_table: ref_table.to_string(),
+                ref_columns: vec![ref_column.to_string()],
+            };
+        let keys = vec![
+            key(
+                "orders_customer",
+                "orders",
+                "customer_id",
+                "customers",
+                "id",
+            ),
+            key(
+                "payments_customer",
+                "payments",
+                "customer",
+                "customers",
+                "id",
+            ),
+            key("customers_region", "customers", "region", "regions", "code"),
+        ];
+        let row = vec![
+            ("id".to_string(), Some("5".to_string())),
+            ("region".to_string(), None),
+            ("name".to_string(), Some("Ada".to_string())),
+        ];
+        let customers = ("public", "customers");
+        let tables = |lookups: Vec<RowLookup>| -> Vec<String> {
+            lookups.into_iter().map(|l| l.table).collect()
+        };
+
+        // Both children reference id; other columns fall back to every child
+        let children = follow_foreign_keys(&keys, customers, "id", &row, true).unwrap();
+        assert_eq!(tables(children), vec!["orders", "payments"]);
+        let children = follow_foreign_keys(&keys, customers, "name", &row, true).unwrap();
+        assert_eq!(tables(children), vec!["orders", "payments"]);
+
+        assert_eq!(
+            follow_foreign_keys(&keys, customers, "name", &row, false),
+            Err("name is not part of a foreign key".to_string())
+        );
+        assert_eq!(
+            follow_foreign_keys(&keys, customers, "region", &row, false),
+            Err("The key is NULL, so it references nothing".to_string())
+        );
+        assert_eq!(
+            follow_foreign_keys(&keys, ("public", "orders"), "id", &row, true),
+            Err("No foreign keys reference orders".to_string())
+        );
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 bytes");
+        assert_eq!(format_size(8192), "8192 bytes");
+        assert_eq!(format_size(16384), "16 kB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5120 MB");
+        assert_eq!(format_size(50 * 1024 * 1024 * 1024), "50 GB");
+    }
+}
@@ -6,6 +6,7 @@
 use ratatui::prelude::Constraint;
 
 use crate::parser::TableData;
+use crate::types::ColumnType;
 use crate::workspace::ViewMode;
 
 /// Application mode for handling different input states.
@@ -24,7 +25,7 @@ pub enum PendingAction {
     None,
     CreateTab {
         name: String,
-        data: TableData,
+        data: Box<TableData>,
         view_mode: ViewMode,
     },
 }
@@ -35,6 +36,8 @@ pub struct PaneRenderData {
     pub name: String,
     /// Filtered display rows (copies for render closure)
     pub display_rows: Vec<Vec<String>>,
+    /// Which cells of each display row are NULL (parallel to display_rows)
+    pub null_cells: Vec<Vec<bool>>,
     /// Headers
     pub headers: Vec<String>,
     /// Total rows (before filter)
@@ -59,4 +62,14 @@ pub struct PaneRenderData {
     pub selected_row: Option<usize>,
     /// Viewport row offset (first row index relative to full filtered dataset)
     pub viewport_row_offset: usize,
+    /// Number of malformed input lines (parse issues)
+    pub issue_count: usize,
+    /// More rows can be fetched from a database cursor
+    pub more_available: bool,
+    /// Type of each column (numeric columns are right-aligned)
+    pub column_types: Vec<ColumnType>,
+    /// Type names shown under the headers (database results without rows)
+    pub header_types: Option<Vec<String>>,
+    /// Display rows drawn highlighted (parallel to display_rows)
+    pub highlighted: Vec<bool>,
 }
@@ -1,133 +1,455 @@
-use crate::parser;
-use std::io::{self, BufRead, BufReader};
+use crate::compress;
+use crate::delimited;
+use crate::detect;
+use crate::json;
+use crate::markdown;
+use crate::parser::{self, InputFormat, ParseIssue};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::mpsc::{self, Receiver};
-use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 /// Maximum number of rows to batch before sending through the channel
 const BATCH_SIZE: usize = 1000;
 
-/// StreamingParser manages background stdin parsing and row delivery via mpsc channel.
+/// Number of batches the background thread may send ahead of the UI.
+///
+/// When the channel is full, reading pauses until the UI receives rows, so
+/// memory use stays bounded no matter how fast the input is.
+const MAX_QUEUED_BATCHES: usize = 8;
+
+/// StreamingParser manages background input parsing and row delivery via mpsc channel.
 ///
 /// This enables non-blocking data loading for large datasets. The background thread reads
-/// stdin line-by-line, parses rows incrementally, and sends them in batches through a channel.
+/// any `Read + Send` source (stdin, files, subprocess output) incrementally, decodes rows in
+/// the detected format or with a custom `RowDecoder`, and sends them in batches through a
+/// channel. Already decoded rows (e.g., from a database cursor) can be streamed with
+/// `from_rows`. Gzip and zstd input is decompressed on the fly.
 ///
 /// Key features:
-/// - Headers parsed synchronously before construction
+/// - Input format detected and headers parsed synchronously before construction
 /// - Background thread for continuous row parsing
-/// - Atomic counters for non-blocking progress tracking
+/// - Bounded channel: reading pauses while the UI is behind (backpressure)
+/// - Atomic counters for non-blocking progress tracking (queued vs consumed)
 /// - Cancellation support via atomic flag
+/// - Read errors kept for the UI instead of silently ending the load
 /// - Thread joined on Drop to prevent data loss
 pub struct StreamingParser {
-    /// Receives batches of parsed rows from background thread
-    receiver: Receiver<Vec<Vec<String>>>,
+    /// Receives batches of parsed rows from background thread (taken on drop)
+    receiver: Option<Receiver<Vec<Vec<Option<String>>>>>,
+    /// Rest of a batch partially returned by `try_recv_batch`
+    partial_batch: VecDeque<Vec<Option<String>>>,
     /// Total number of rows parsed so far (updated by background thread)
     row_count: Arc<AtomicUsize>,
+    /// Number of rows returned by `try_recv_batch` so far
+    consumed_count: usize,
     /// Cancellation signal (set by main thread, read by background thread)
     cancelled: Arc<AtomicBool>,
     /// Set to true when background thread finishes
     complete: Arc<AtomicBool>,
+    /// Error that stopped the background thread early (e.g., corrupt compressed data)
+    error: Arc<Mutex<Option<String>>>,
     /// Thread handle for joining on drop
     thread_handle: Option<JoinHandle<io::Result<()>>>,
-    /// Parsed column headers (available immediately after construction)
-    headers: Vec<String>,
+    /// Column headers (available after construction, may grow for JSON and expanded input)
+    headers: Arc<Mutex<Vec<String>>>,
+    /// Explanation shown to the user when input fell back to raw lines
+    notice: Option<String>,
+    /// Malformed lines found by the row decoder and not yet taken
+    issues: Arc<Mutex<Vec<ParseIssue>>>,
+    /// Whether input is followed for appended data (never completes on its own)
+    following: bool,
+}
+
+/// How often a followed file is checked for appended data
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How long followed input may be idle before a partial batch is sent
+const FOLLOW_FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Number of lines buffered up front to detect the format and parse headers
+const PROBE_LINES: usize = 20;
+
+/// Boxed iterator of parsed rows (`None` cells are NULL), consumed by the
+/// background thread
+type RowIter = Box<dyn Iterator<Item = io::Result<Vec<Option<String>>>> + Send>;
+
+/// How input is parsed by `StreamingParser`.
+#[derive(Clone, Debug, Default)]
+pub struct ReadOptions {
+    /// Input format, or None to detect it from the first lines
+    pub format: Option<InputFormat>,
+    /// Field delimiter overriding the default of CSV/TSV input
+    pub delimiter: Option<u8>,
+    /// Text that stands for NULL (psql's `\pset null`); not used for JSON,
+    /// which has its own `null`
+    pub null_string: Option<String>,
 }
 
 impl StreamingParser {
     /// Create a StreamingParser from stdin.
     ///
+    /// Returns the same results as `from_reader`.
+    pub fn from_stdin(options: &ReadOptions) -> io::Result<Option<Self>> {
+        Self::from_reader(io::stdin(), options)
+    }
+
+    /// Create a StreamingParser from any reader.
+    ///
+    /// Without an explicit format in `options`, the format is detected from
+    /// the first lines.
+    ///
     /// Returns:
-    /// - `Ok(Some(parser))` if headers found and parsing started
-    /// - `Ok(None)` if stdin doesn't contain valid psql headers
+    /// - `Ok(Some(parser))` if input was found and parsing started
+    /// - `Ok(None)` if the input is empty
     /// - `Err(e)` on IO errors
     ///
-    /// The headers are parsed synchronously (blocking) from the first few lines.
-    /// The background thread is spawned to continue reading remaining data.
-    pub fn from_stdin() -> io::Result<Option<Self>> {
-        let stdin = io::stdin();
-        let mut reader = BufReader::new(stdin);
-
-        // Read first lines to find headers (up to 20 lines)
-        let mut collected_lines = Vec::new();
-        let mut line_strings = Vec::new(); // Keep owned strings for later parsing
-        for _ in 0..20 {
-            let mut line = String::new();
-            match reader.read_line(&mut line) {
-                Ok(0) => break, // EOF
-                Ok(_) => {
-                    line_strings.push(line.clone());
-                    collected_lines.push(line);
-                }
-                Err(e) => return Err(e),
+    /// The first lines are buffered to detect the format and parse headers
+    /// synchronously (blocking). The background thread is spawned to continue
+    /// reading remaining data. If the format cannot be recognized, input is
+    /// shown as raw lines and `notice()` explains why.
+    pub fn from_reader<R>(reader: R, options: &ReadOptions) -> io::Result<Option<Self>>
+    where
+        R: Read + Send + 'static,
+    {
+        Self::from_buf_reader(BufReader::new(reader), options.clone(), None)
+    }
+
+    /// Create a StreamingParser that decodes lines of `reader` with a custom decoder.
+    ///
+    /// `headers` are the initial columns. A decoder that discovers columns while
+    /// reading reports all of them through `RowDecoder::headers`, which replaces
+    /// `headers` once it is longer. Compressed input is decoded first.
+    pub fn with_decoder<R>(
+        reader: R,
+        headers: Vec<String>,
+        decoder: Box<dyn RowDecoder>,
+    ) -> io::Result<Self>
+    where
+        R: Read + Send + 'static,
+    {
+        let input = compress::decompress(BufReader::new(reader))?;
+        let shared_headers = Arc::new(Mutex::new(headers));
+        let rows = DecodedRows::new(input.lines(), decoder, Arc::clone(&shared_headers));
+        let issues = Arc::clone(&rows.shared_issues);
+        let mut parser = Self::spawn(shared_headers, Box::new(rows), Arc::default(), false);
+        parser.issues = issues;
+        Ok(parser)
+    }
+
+    /// Create a StreamingParser over rows that are already decoded, such as a
+    /// database cursor or another parser.
+    ///
+    /// An `Err` from `rows` stops loading and is reported by `error()`.
+    /// `None` cells are NULL.
+    pub fn from_rows<I>(headers: Vec<String>, rows: I) -> Self
+    where
+        I: Iterator<Item = io::Result<Vec<Option<String>>>> + Send + 'static,
+    {
+        Self::spawn(
+            Arc::new(Mutex::new(headers)),
+            Box::new(rows),
+            Arc::default(),
+            false,
+        )
+    }
+
+    /// Create a StreamingParser reading a file.
+    ///
+    /// Without an explicit format, the file extension is used when it names a
+    /// known format, otherwise the format is detected from the first lines.
+    /// Returns `Ok(None)` if the file is empty.
+    pub fn from_path(path: &Path, options: &ReadOptions) -> io::Result<Option<Self>> {
+        let file = File::open(path)?;
+        let options = Self::options_for_path(path, options);
+        Self::from_buf_reader(BufReader::new(file), options, None)
+    }
+
+    /// Create a StreamingParser that keeps reading a file as it grows, like `tail -f`.
+    ///
+    /// Existing content is loaded as with `from_path`, then new lines are parsed as
+    /// they are appended. An empty file blocks until its first lines are written,
+    /// so it never returns `Ok(None)`. The parser never completes on its own;
+    /// `cancel()` (or dropping it) stops following.
+    pub fn follow_path(path: &Path, options: &ReadOptions) -> io::Result<Option<Self>> {
+        let file = File::open(path)?;
+        let options = Self::options_for_path(path, options);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        Self::from_buf_reader(BufReader::new(file), options, Some(cancelled))
+    }
+
+    /// Fill in the format from the file extension when not given explicitly.
+    fn options_for_path(path: &Path, options: &ReadOptions) -> ReadOptions {
+        ReadOptions {
+            format: options.format.or_else(|| InputFormat::from_path(path)),
+            ..options.clone()
+        }
+    }
+
+    /// Detect the format (unless given), parse headers, and start streaming rows.
+    ///
+    /// Compressed input is decoded first. With `follow` set, reading waits for
+    /// more data at end of input until the given cancellation flag is set.
+    fn from_buf_reader<R>(
+        reader: R,
+        options: ReadOptions,
+        follow: Option<Arc<AtomicBool>>,
+    ) -> io::Result<Option<Self>>
+    where
+        R: BufRead + Send + 'static,
+    {
+        let mut reader = reader;
+        let waiting = |follow: &Option<Arc<AtomicBool>>| {
+            follow
+                .as_ref()
+                .is_some_and(|cancelled| !cancelled.load(Ordering::Relaxed))
+        };
+        // A followed file may not have been written yet
+        while waiting(&follow) && reader.fill_buf()?.is_empty() {
+            thread::sleep(FOLLOW_POLL_INTERVAL);
+        }
+        let mut reader = compress::decompress(reader)?;
+
+        // Read first lines to detect the format and find headers. When
+        // following, wait until they are more than blank lines.
+        let mut probe: Vec<String> = Vec::new();
+        loop {
+            let blank = probe.iter().all(|line| line.trim().is_empty());
+            if probe.len() >= PROBE_LINES && (!blank || !waiting(&follow)) {
+                break;
+            }
+            // Continue a line that was cut off at the end of input
+            let mut line = match probe.last() {
+                Some(last) if !last.ends_with('\n') => probe.pop().unwrap_or_default(),
+                _ => String::new(),
+            };
+            let read = reader.read_line(&mut line)?;
+            if !line.is_empty() {
+                probe.push(line);
+            }
+            if read > 0 {
+                continue;
+            }
+            // End of input (for now)
+            if !blank || !waiting(&follow) {
+                break;
             }
+            thread::sleep(FOLLOW_POLL_INTERVAL);
         }
 
-        // Convert to &str slice for parse_psql_header
-        let line_refs: Vec<&str> = line_strings.iter().map(|s| s.as_str()).collect();
+        if probe.iter().all(|line| line.trim().is_empty()) {
+            return Ok(None);
+        }
+
+        let line_refs: Vec<&str> = probe.iter().map(|s| s.as_str()).collect();
+        let format = options.format.or_else(|| detect::detect_format(&line_refs));
+        let delimiter = options
+            .delimiter
+            .or_else(|| format.and_then(InputFormat::default_delimiter));
 
-        // Parse headers
-        let (headers, data_start_index) = match parser::parse_psql_header(&line_refs) {
-            Some(result) => result,
-            None => return Ok(None), // No valid headers found
+        // Headers are parsed from the probe so a failed guess can still fall back
+        let header = match (format, delimiter) {
+            (Some(InputFormat::Csv | InputFormat::Tsv), Some(delimiter)) => {
+                let text = probe.concat();
+                let mut csv_reader = delimited::delimited_reader(text.as_bytes(), delimiter);
+                delimited::read_delimited_header(&mut csv_reader)
+                    .ok()
+                    .flatten()
+                    .map(|headers| (headers, 0))
+            }
+            (Some(InputFormat::Psql), _) => parser::parse_psql_header(&line_refs),
+            (Some(InputFormat::PsqlUnaligned), _) => {
+                parser::parse_psql_unaligned_header(&line_refs)
+            }
+            (Some(InputFormat::Markdown), _) => markdown::parse_markdown_header(&line_refs),
+            (Some(InputFormat::Json | InputFormat::Ndjson), _) => {
+                // Seed columns from values completed within the probe
+                let mut decoder = JsonDecoder::new(&[]);
+                for line in &line_refs {
+                    decoder.push_line(parser::trim_line_ending(line));
+                }
+                Some((decoder.headers().to_vec(), 0))
+            }
+            (Some(InputFormat::PsqlExpanded), _) => {
+                if !line_refs
+                    .iter()
+                    .any(|line| parser::is_expanded_record_header(line))
+                {
+                    None
+                } else {
+                    // Columns of a record cut off by the probe are added while streaming
+                    let mut decoder = parser::ExpandedRecordParser::new();
+                    for line in &line_refs {
+                        decoder.push_line(line);
+                    }
+                    Some((decoder.headers().to_vec(), 0))
+                }
+            }
+            _ => None,
         };
 
-        let column_count = headers.len();
+        // Replay the probe lines in front of the rest of the stream
+        let following = follow.is_some();
+        let cancelled = follow.unwrap_or_default();
+        let rest: Box<dyn Read + Send> = if following {
+            Box::new(FollowReader {
+                inner: reader,
+                cancelled: Arc::clone(&cancelled),
+            })
+        } else {
+            Box::new(reader)
+        };
+        let input = BufReader::new(io::Cursor::new(probe.concat().into_bytes()).chain(rest));
 
-        // Create channel for row batches
-        let (sender, receiver) = mpsc::channel();
+        let shared_headers = Arc::new(Mutex::new(Vec::new()));
+        let mut issues = Arc::default();
+
+        let (headers, rows, notice): (Vec<String>, RowIter, Option<String>) = match (format, header)
+        {
+            (Some(InputFormat::Csv | InputFormat::Tsv), Some(_)) => {
+                let mut csv_reader = delimited::delimited_reader(input, delimiter.unwrap_or(b','));
+                let headers = match delimited::read_delimited_header(&mut csv_reader)? {
+                    Some(headers) => headers,
+                    None => return Ok(None),
+                };
+                let rows = csv_reader.into_byte_records().map(|record| {
+                    record
+                        .map(|r| parser::present_cells(delimited::record_to_row(&r)))
+                        .map_err(delimited::csv_error_to_io)
+                });
+                (headers, Box::new(rows), None)
+            }
+            (Some(format), Some((headers, data_start_index))) => {
+                let decoder: Box<dyn RowDecoder> = match format {
+                    InputFormat::Psql => Box::new(parser::AlignedRowParser::new(
+                        line_refs[data_start_index - 1],
+                        data_start_index,
+                    )),
+                    InputFormat::PsqlExpanded => Box::new(parser::ExpandedRecordParser::new()),
+                    InputFormat::Json | InputFormat::Ndjson => Box::new(JsonDecoder::new(&headers)),
+                    InputFormat::Markdown => Box::new(markdown::parse_markdown_line),
+                    _ => Box::new(parser::parse_psql_unaligned_line),
+                };
+                let rows = DecodedRows::new(
+                    input.lines().skip(data_start_index),
+                    decoder,
+                    Arc::clone(&shared_headers),
+                );
+                issues = Arc::clone(&rows.shared_issues);
+                (headers, Box::new(rows), None)
+            }
+            (format, _) => {
+                let notice = match format {
+                    Some(format) => {
+                        format!(
+                            "Could not parse input as {}, showing raw lines",
+                            format.label()
+                        )
+                    }
+                    None => {
+                        "Unrecognized input format, showing raw lines (see --format)".to_string()
+                    }
+                };
+                let rows = input.lines().map(|line| line.map(|l| vec![Some(l)]));
+                (vec!["line".to_string()], Box::new(rows), Some(notice))
+            }
+        };
+
+        // JSON has its own null, everything else can mark NULL with a display string
+        let rows: RowIter = match options.null_string {
+            Some(null_string)
+                if !matches!(format, Some(InputFormat::Json | InputFormat::Ndjson)) =>
+            {
+                Box::new(rows.map(move |row_result| {
+                    row_result.map(|row| parser::mark_nulls(row, &null_string))
+                }))
+            }
+            _ => rows,
+        };
+
+        *shared_headers.lock().unwrap() = headers;
+        let mut parser = Self::spawn(shared_headers, rows, cancelled, following);
+        parser.notice = notice;
+        parser.issues = issues;
+        Ok(Some(parser))
+    }
+
+    /// Start the background thread that drains `rows` into the channel.
+    ///
+    /// `headers` is shared with the row iterator so formats whose columns are
+    /// discovered while reading (JSON, psql expanded) can add to it. When
+    /// `following`, partial batches are flushed whenever input goes idle.
+    fn spawn(
+        headers: Arc<Mutex<Vec<String>>>,
+        rows: RowIter,
+        cancelled: Arc<AtomicBool>,
+        following: bool,
+    ) -> Self {
+        // Create bounded channel for row batches; sending blocks while it is full
+        let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED_BATCHES);
 
         // Create atomic counters and flags
         let row_count = Arc::new(AtomicUsize::new(0));
-        let cancelled = Arc::new(AtomicBool::new(false));
         let complete = Arc::new(AtomicBool::new(false));
+        let error = Arc::new(Mutex::new(None));
 
         // Clone for thread
         let row_count_clone = Arc::clone(&row_count);
         let cancelled_clone = Arc::clone(&cancelled);
         let complete_clone = Arc::clone(&complete);
+        let error_clone = Arc::clone(&error);
 
-        // Parse any data rows already collected (between data_start_index and end of collected lines)
-        let mut initial_batch = Vec::new();
-        for line in line_refs.iter().skip(data_start_index) {
-            if let Some(row) = parser::parse_psql_line(line, column_count) {
-                initial_batch.push(row);
-            }
-        }
-
-        // Send initial batch if we have any rows
-        if !initial_batch.is_empty() {
-            let count = initial_batch.len();
-            row_count.fetch_add(count, Ordering::Relaxed);
-            let _ = sender.send(initial_batch);
-        }
-
-        // Spawn background thread to continue reading remaining stdin
+        // Spawn background thread to continue reading remaining input
         let thread_handle = thread::spawn(move || -> io::Result<()> {
             let mut current_batch = Vec::new();
+            let mut result = Ok(());
+
+            // `None` marks a pause in followed input
+            let events: Box<dyn Iterator<Item = io::Result<Option<Vec<Option<String>>>>>> =
+                if following {
+                    Box::new(FollowedRows::spawn(rows))
+                } else {
+                    Box::new(rows.map(|row_result| row_result.map(Some)))
+                };
 
-            // Continue reading from the already-locked reader
-            for line_result in reader.lines() {
+            for event in events {
                 // Check cancellation flag
                 if cancelled_clone.load(Ordering::Relaxed) {
                     break;
                 }
 
-                let line = line_result?;
-
-                // Parse the line
-                if let Some(row) = parser::parse_psql_line(&line, column_count) {
-                    current_batch.push(row);
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        // Keep the rows read so far and stop at the error
+                        result = Err(e);
+                        break;
+                    }
+                };
 
-                    // Send batch when it reaches BATCH_SIZE
-                    if current_batch.len() >= BATCH_SIZE {
-                        row_count_clone.fetch_add(current_batch.len(), Ordering::Relaxed);
-                        if sender.send(current_batch.clone()).is_err() {
-                            // Channel disconnected (receiver dropped)
-                            break;
+                match event {
+                    Some(row) => {
+                        current_batch.push(row);
+                        // Send batch when it reaches BATCH_SIZE
+                        if current_batch.len() < BATCH_SIZE {
+                            continue;
                         }
-                        current_batch.clear();
                     }
+                    // Input is idle: send what we have so the UI shows it now
+                    None if !current_batch.is_empty() => {}
+                    None => continue,
+                }
+
+                row_count_clone.fetch_add(current_batch.len(), Ordering::Relaxed);
+                let batch = std::mem::replace(&mut current_batch, Vec::with_capacity(BATCH_SIZE));
+                if sender.send(batch).is_err() {
+                    // Channel disconnected (receiver dropped)
+                    break;
                 }
             }
 
@@ -137,55 +459,88 @@ impl StreamingParser {
                 let _ = sender.send(current_batch);
             }
 
-            // Mark as complete
+            if let Err(ref e) = result {
+                *error_clone.lock().unwrap() = Some(e.to_string());
+            }
+
+            // Mark as complete (also after an error, so the UI stops waiting)
             complete_clone.store(true, Ordering::Release);
 
-            Ok(())
+            result
         });
 
-        Ok(Some(StreamingParser {
-            receiver,
+        StreamingParser {
+            receiver: Some(receiver),
+            partial_batch: VecDeque::new(),
             row_count,
+            consumed_count: 0,
             cancelled,
             complete,
+            error,
             thread_handle: Some(thread_handle),
             headers,
-        }))
+            notice: None,
+            issues: Arc::default(),
+            following,
+        }
     }
 
     /// Try to receive up to `max_rows` from the channel without blocking.
     ///
-    /// Returns a Vec of rows (each row is Vec<String>).
+    /// Returns a Vec of rows (each row is Vec<Option<String>>, `None` for NULL).
     /// Returns empty Vec if no data available.
-    pub fn try_recv_batch(&self, max_rows: usize) -> Vec<Vec<String>> {
+    ///
+    /// Rows beyond `max_rows` in a received batch are kept for the next call,
+    /// so no rows are lost. Receiving frees channel space for the background
+    /// thread to continue reading.
+    pub fn try_recv_batch(&mut self, max_rows: usize) -> Vec<Vec<Option<String>>> {
         let mut rows = Vec::new();
 
-        // Drain messages from channel up to max_rows
         while rows.len() < max_rows {
-            match self.receiver.try_recv() {
-                Ok(batch) => {
-                    // Add rows from this batch, respecting max_rows limit
-                    for row in batch {
-                        if rows.len() >= max_rows {
-                            break;
-                        }
-                        rows.push(row);
-                    }
+            if self.partial_batch.is_empty() {
+                let Some(receiver) = self.receiver.as_ref() else {
+                    break;
+                };
+                match receiver.try_recv() {
+                    Ok(batch) => self.partial_batch = batch.into(),
+                    Err(_) => break, // No more batches or channel disconnected
                 }
-                Err(_) => break, // No more messages or channel disconnected
             }
+
+            // Take rows from the current batch, respecting max_rows limit
+            let take = (max_rows - rows.len()).min(self.partial_batch.len());
+            rows.extend(self.partial_batch.drain(..take));
         }
 
+        self.consumed_count += rows.len();
         rows
     }
 
     /// Get the total number of rows parsed so far.
     ///
     /// This is non-blocking and can be called while parsing is in progress.
+    /// It includes rows not yet received (see `rows_queued`).
     pub fn total_rows_parsed(&self) -> usize {
         self.row_count.load(Ordering::Relaxed)
     }
 
+    /// Get the number of rows returned by `try_recv_batch` so far.
+    pub fn rows_consumed(&self) -> usize {
+        self.consumed_count
+    }
+
+    /// Get the number of rows parsed but not yet returned by `try_recv_batch`.
+    ///
+    /// At most a few batches, since reading pauses while the channel is full.
+    pub fn rows_queued(&self) -> usize {
+        self.total_rows_parsed().saturating_sub(self.consumed_count)
+    }
+
+    /// Check if all input has been parsed and every row received.
+    pub fn is_drained(&self) -> bool {
+        self.is_complete() && self.rows_queued() == 0
+    }
+
     /// Request cancellation of the background parsing thread.
     ///
     /// The thread will stop reading and exit promptly.
@@ -200,9 +555,297 @@ impl StreamingParser {
         self.complete.load(Ordering::Acquire)
     }
 
-    /// Get a reference to the parsed column headers.
-    pub fn headers(&self) -> &[String] {
-        &self.headers
+    /// Get the error that stopped reading early, if any.
+    ///
+    /// Rows read before the error are still delivered. Set before
+    /// `is_complete()` becomes true.
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    /// Get a snapshot of the column headers.
+    ///
+    /// Headers are available immediately after construction. For JSON and psql
+    /// expanded input, columns are appended as they are found, before rows using
+    /// them are sent.
+    pub fn headers(&self) -> Vec<String> {
+        self.headers.lock().unwrap().clone()
+    }
+
+    /// Get the current number of columns without copying the headers.
+    pub fn header_count(&self) -> usize {
+        self.headers.lock().unwrap().len()
+    }
+
+    /// Check if the parser follows its input for appended data (`follow_path`).
+    pub fn is_following(&self) -> bool {
+        self.following
+    }
+
+    /// Take the malformed lines found since the last call (psql aligned input).
+    ///
+    /// Issues are recorded before the rows that follow them are sent.
+    pub fn take_issues(&self) -> Vec<ParseIssue> {
+        std::mem::take(&mut *self.issues.lock().unwrap())
+    }
+
+    /// Get the fallback notice, if the input format could not be parsed.
+    pub fn notice(&self) -> Option<&str> {
+        self.notice.as_deref()
+    }
+}
+
+/// Decodes lines of input into rows for `StreamingParser`.
+///
+/// One row can span several lines (psql aligned and expanded, JSON), so each
+/// line returns the rows it completes. Columns may also be discovered while
+/// reading (psql expanded, JSON). Any `FnMut(&str) -> Option<Vec<String>>`
+/// is a decoder for formats with one row per line. `None` cells are NULL.
+pub trait RowDecoder: Send {
+    /// Feed one line of input (without line ending), returning the rows it completes.
+    fn push_line(&mut self, line: &str) -> Vec<Vec<Option<String>>>;
+
+    /// Flush any rows left at end of input.
+    fn finish(&mut self) -> Vec<Vec<Option<String>>> {
+        Vec::new()
+    }
+
+    /// Column names seen so far (empty when columns are fixed up front).
+    fn headers(&self) -> &[String] {
+        &[]
+    }
+
+    /// Take the malformed lines found since the last call.
+    fn take_issues(&mut self) -> Vec<ParseIssue> {
+        Vec::new()
+    }
+}
+
+impl<F> RowDecoder for F
+where
+    F: FnMut(&str) -> Option<Vec<String>> + Send,
+{
+    fn push_line(&mut self, line: &str) -> Vec<Vec<Option<String>>> {
+        self(line).map(parser::present_cells).into_iter().collect()
+    }
+}
+
+/// Splits lines into JSON values and flattens them into rows.
+struct JsonDecoder {
+    splitter: json::JsonValueSplitter,
+    builder: json::JsonRowBuilder,
+}
+
+impl JsonDecoder {
+    fn new(headers: &[String]) -> Self {
+        JsonDecoder {
+            splitter: json::JsonValueSplitter::new(),
+            builder: json::JsonRowBuilder::with_headers(headers),
+        }
+    }
+}
+
+impl RowDecoder for JsonDecoder {
+    fn push_line(&mut self, line: &str) -> Vec<Vec<Option<String>>> {
+        // Values that are not valid JSON are skipped
+        self.splitter
+            .push_line(line)
+            .iter()
+            .filter_map(|text| self.builder.row_from_text(text))
+            .collect()
+    }
+
+    fn finish(&mut self) -> Vec<Vec<Option<String>>> {
+        self.splitter
+            .finish()
+            .and_then(|text| self.builder.row_from_text(&text))
+            .into_iter()
+            .collect()
+    }
+
+    fn headers(&self) -> &[String] {
+        self.builder.headers()
+    }
+}
+
+impl RowDecoder for parser::AlignedRowParser {
+    fn push_line(&mut self, line: &str) -> Vec<Vec<Option<String>>> {
+        parser::AlignedRowParser::push_line(self, line)
+            .map(parser::present_cells)
+            .into_iter()
+            .collect()
+    }
+
+    fn finish(&mut self) -> Vec<Vec<Option<String>>> {
+        parser::AlignedRowParser::finish(self)
+            .map(parser::present_cells)
+            .into_iter()
+            .collect()
+    }
+
+    fn take_issues(&mut self) -> Vec<ParseIssue> {
+        parser::AlignedRowParser::take_issues(self)
+    }
+}
+
+impl RowDecoder for parser::ExpandedRecordParser {
+    fn push_line(&mut self, line: &str) -> Vec<Vec<Option<String>>> {
+        parser::ExpandedRecordParser::push_line(self, line)
+            .map(parser::present_cells)
+            .into_iter()
+            .collect()
+    }
+
+    fn finish(&mut self) -> Vec<Vec<Option<String>>> {
+        parser::ExpandedRecordParser::finish(self)
+            .map(parser::present_cells)
+            .into_iter()
+            .collect()
+    }
+
+    fn headers(&self) -> &[String] {
+        parser::ExpandedRecordParser::headers(self)
+    }
+}
+
+/// Row iterator over a `RowDecoder`.
+///
+/// When the decoder has found new columns, the shared headers are updated
+/// before the next row is yielded. Parse issues are moved to `shared_issues`
+/// as soon as they are found.
+struct DecodedRows<L> {
+    lines: L,
+    decoder: Box<dyn RowDecoder>,
+    /// Rows completed by the last line but not yet yielded
+    pending: VecDeque<Vec<Option<String>>>,
+    shared_headers: Arc<Mutex<Vec<String>>>,
+    shared_issues: Arc<Mutex<Vec<ParseIssue>>>,
+    finished: bool,
+}
+
+impl<L> DecodedRows<L> {
+    fn new(
+        lines: L,
+        decoder: Box<dyn RowDecoder>,
+        shared_headers: Arc<Mutex<Vec<String>>>,
+    ) -> Self {
+        DecodedRows {
+            lines,
+            decoder,
+            pending: VecDeque::new(),
+            shared_headers,
+            shared_issues: Arc::default(),
+            finished: false,
+        }
+    }
+}
+
+impl<L> Iterator for DecodedRows<L>
+where
+    L: Iterator<Item = io::Result<String>>,
+{
+    type Item = io::Result<Vec<Option<String>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                let mut shared = self.shared_headers.lock().unwrap();
+                if shared.len() < self.decoder.headers().len() {
+                    *shared = self.decoder.headers().to_vec();
+                }
+                return Some(Ok(row));
+            }
+
+            if self.finished {
+                return None;
+            }
+
+            match self.lines.next() {
+                Some(Ok(line)) => self.pending.extend(self.decoder.push_line(&line)),
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.finished = true;
+                    self.pending.extend(self.decoder.finish());
+                }
+            }
+
+            let issues = self.decoder.take_issues();
+            if !issues.is_empty() {
+                self.shared_issues.lock().unwrap().extend(issues);
+            }
+        }
+    }
+}
+
+/// Reader that waits for more data at end of input instead of stopping (like `tail -f`).
+///
+/// Reports end of input only once `cancelled` is set.
+struct FollowReader<R> {
+    inner: R,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<R: Read> Read for FollowReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n > 0 || buf.is_empty() || self.cancelled.load(Ordering::Relaxed) {
+                return Ok(n);
+            }
+            thread::sleep(FOLLOW_POLL_INTERVAL);
+        }
+    }
+}
+
+/// Rows of followed input, read on a separate thread.
+///
+/// Reading blocks while waiting for appended data, so rows are passed through
+/// a channel and `Ok(None)` is yielded whenever no row arrives within
+/// `FOLLOW_FLUSH_INTERVAL`. This lets the batching loop send partial batches
+/// and check for cancellation while input is idle.
+struct FollowedRows {
+    receiver: Option<Receiver<io::Result<Vec<Option<String>>>>>,
+    reader_handle: Option<JoinHandle<()>>,
+}
+
+impl FollowedRows {
+    fn spawn(rows: RowIter) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(BATCH_SIZE);
+        let reader_handle = thread::spawn(move || {
+            for row_result in rows {
+                if sender.send(row_result).is_err() {
+                    // Batching loop stopped
+                    break;
+                }
+            }
+        });
+
+        FollowedRows {
+            receiver: Some(receiver),
+            reader_handle: Some(reader_handle),
+        }
+    }
+}
+
+impl Iterator for FollowedRows {
+    type Item = io::Result<Option<Vec<Option<String>>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.receiver.as_ref()?.recv_timeout(FOLLOW_FLUSH_INTERVAL) {
+            Ok(row_result) => Some(row_result.map(Some)),
+            Err(RecvTimeoutError::Timeout) => Some(Ok(None)),
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+}
+
+impl Drop for FollowedRows {
+    fn drop(&mut self) {
+        // Close the channel first so a reader blocked on a full channel exits
+        self.receiver.take();
+        if let Some(handle) = self.reader_handle.take() {
+            let _ = handle.join();
+        }
     }
 }
 
@@ -211,9 +854,411 @@ impl Drop for StreamingParser {
         // Signal cancellation (in case it wasn't already)
         self.cancelled.store(true, Ordering::Relaxed);
 
+        // Close the channel so a thread blocked on a full channel exits
+        self.receiver.take();
+
         // Wait for thread to finish
         if let Some(handle) = self.thread_handle.take() {
             let _ = handle.join();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Write;
+    use std::time::Instant;
+
+    /// Wait for the background thread and collect every row it sent.
+    fn collect_cells(parser: &mut StreamingParser) -> Vec<Vec<Option<String>>> {
+        let mut rows = Vec::new();
+        while !parser.is_drained() {
+            let batch = parser.try_recv_batch(usize::MAX);
+            if batch.is_empty() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            rows.extend(batch);
+        }
+        rows
+    }
+
+    /// Like `collect_cells`, with NULL cells shown as empty strings.
+    fn collect_rows(parser: &mut StreamingParser) -> Vec<Vec<String>> {
+        collect_cells(parser)
+            .into_iter()
+            .map(|row| row.into_iter().map(Option::unwrap_or_default).collect())
+            .collect()
+    }
+
+    /// Poll `rows_queued` until it stops rising (the reader is blocked on a
+    /// full channel), and return it.
+    fn wait_for_queue_to_settle(parser: &StreamingParser) -> usize {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut queued = parser.rows_queued();
+        let mut unchanged_polls = 0;
+        while unchanged_polls < 10 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+            let now = parser.rows_queued();
+            if now == queued && now > 0 {
+                unchanged_polls += 1;
+            } else {
+                unchanged_polls = 0;
+            }
+            queued = now;
+        }
+        queued
+    }
+
+    fn from_text(text: &str, format: Option<InputFormat>) -> StreamingParser {
+        let options = ReadOptions {
+            format,
+            ..ReadOptions::default()
+        };
+        StreamingParser::from_reader(io::Cursor::new(text.to_string()), &options)
+            .unwrap()
+            .expect("input should not be empty")
+    }
+
+    /// Reader that fails after returning its data.
+    struct FailingReader {
+        data: io::Cursor<Vec<u8>>,
+    }
+
+    impl Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.data.read(buf)? {
+                0 => Err(io::Error::new(io::ErrorKind::InvalidData, "corrupt input")),
+                n => Ok(n),
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_reader_psql() {
+        let mut parser = from_text(
+            " id | name\n----+-------\n  1 | Alice\n  2 | Bob\n(2 rows)\n",
+            None,
+        );
+
+        assert_eq!(parser.headers(), vec!["id", "name"]);
+        assert_eq!(
+            collect_rows(&mut parser),
+            vec![vec!["1", "Alice"], vec!["2", "Bob"]]
+        );
+        assert_eq!(parser.total_rows_parsed(), 2);
+        assert!(parser.notice().is_none());
+        assert!(parser.error().is_none());
+    }
+
+    #[test]
+    fn test_from_reader_csv_many_batches() {
+        let mut text = String::from("id,name\n");
+        for i in 0..(BATCH_SIZE * 2 + 5) {
+            text.push_str(&format!("{},row{}\n", i, i));
+        }
+
+        let mut parser = from_text(&text, Some(InputFormat::Csv));
+        let rows = collect_rows(&mut parser);
+
+        assert_eq!(rows.len(), BATCH_SIZE * 2 + 5);
+        assert_eq!(
+            rows[BATCH_SIZE],
+            vec![BATCH_SIZE.to_string(), format!("row{}", BATCH_SIZE)]
+        );
+    }
+
+    #[test]
+    fn test_from_reader_reports_parse_issues() {
+        let mut text = String::from(" id | name\n----+------\n");
+        for i in 0..PROBE_LINES {
+            text.push_str(&format!(" {:>2} | a\n", i));
+        }
+        text.push_str("ERROR:  canceling statement due to user request\n");
+
+        let mut parser = from_text(&text, None);
+        let rows = collect_rows(&mut parser);
+        assert_eq!(rows.len(), PROBE_LINES);
+
+        let issues = parser.take_issues();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line_number, PROBE_LINES + 3);
+        assert_eq!(issues[0].action, parser::IssueAction::Rejected);
+        assert!(parser.take_issues().is_empty());
+    }
+
+    #[test]
+    fn test_from_reader_ndjson_discovers_columns() {
+        let mut text = "{\"a\": 1}\n".repeat(PROBE_LINES);
+        text.push_str("{\"a\": 2, \"b\": 3}\n");
+
+        let mut parser = from_text(&text, None);
+        // The probe only saw "a"; "b" may already have been found by the reader thread
+        assert_eq!(parser.headers()[0], "a");
+
+        let rows = collect_rows(&mut parser);
+        assert_eq!(rows.len(), PROBE_LINES + 1);
+        assert_eq!(rows[PROBE_LINES], vec!["2", "3"]);
+        assert_eq!(parser.headers(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_from_reader_null_string() {
+        let options = ReadOptions {
+            null_string: Some("(null)".to_string()),
+            ..ReadOptions::default()
+        };
+        let text = " id | note\n----+--------\n  1 | (null)\n  2 |\n";
+        let mut parser = StreamingParser::from_reader(io::Cursor::new(text), &options)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            collect_cells(&mut parser),
+            vec![
+                vec![Some("1".to_string()), None],
+                vec![Some("2".to_string()), Some(String::new())]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_reader_nul_byte_is_not_null() {
+        let text = "a,b\n\0,x\0y\n";
+        let mut parser =
+            StreamingParser::from_reader(io::Cursor::new(text), &ReadOptions::default())
+                .unwrap()
+                .unwrap();
+
+        assert_eq!(
+            collect_cells(&mut parser),
+            vec![vec![Some("\0".to_string()), Some("x\0y".to_string())]]
+        );
+    }
+
+    #[test]
+    fn test_follow_waits_for_an_empty_file() {
+        let dir = std::env::temp_dir().join(format!("pte-follow-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("metrics.csv");
+        File::create(&path).unwrap();
+
+        let writer_path = path.clone();
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            let mut file = fs::OpenOptions::new()
+                .append(true)
+                .open(writer_path)
+                .unwrap();
+            file.write_all(b"\n").unwrap();
+            thread::sleep(Duration::from_millis(300));
+            file.write_all(b"t,value\n1,2\n").unwrap();
+        });
+
+        let mut parser = StreamingParser::follow_path(&path, &ReadOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(parser.headers(), vec!["t", "value"]);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut rows = Vec::new();
+        while rows.is_empty() && Instant::now() < deadline {
+            rows = parser.try_recv_batch(usize::MAX);
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(
+            rows,
+            vec![parser::present_cells(vec!["1".into(), "2".into()])]
+        );
+        assert!(!parser.is_complete());
+
+        parser.cancel();
+        writer.join().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_from_reader_empty_input() {
+        let parser =
+            StreamingParser::from_reader(io::Cursor::new("\n\n"), &ReadOptions::default()).unwrap();
+        assert!(parser.is_none());
+    }
+
+    #[test]
+    fn test_from_reader_unrecognized_falls_back_to_lines() {
+        let mut parser = from_text("hello\nworld\n", None);
+
+        assert_eq!(parser.headers(), vec!["line"]);
+        assert!(parser.notice().is_some());
+        assert_eq!(
+            collect_rows(&mut parser),
+            vec![vec!["hello"], vec!["world"]]
+        );
+    }
+
+    #[test]
+    fn test_from_reader_gzip() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"id\tname\n1\tAlice\n").unwrap();
+        let bytes = encoder.finish().unwrap();
+
+        let mut parser =
+            StreamingParser::from_reader(io::Cursor::new(bytes), &ReadOptions::default())
+                .unwrap()
+                .unwrap();
+        assert_eq!(parser.headers(), vec!["id", "name"]);
+        assert_eq!(collect_rows(&mut parser), vec![vec!["1", "Alice"]]);
+    }
+
+    #[test]
+    fn test_read_error_reported() {
+        let mut text = String::from("id,name\n");
+        for i in 0..(PROBE_LINES * 2) {
+            text.push_str(&format!("{},row\n", i));
+        }
+        let reader = FailingReader {
+            data: io::Cursor::new(text.into_bytes()),
+        };
+
+        let options = ReadOptions {
+            format: Some(InputFormat::Csv),
+            ..ReadOptions::default()
+        };
+        let mut parser = StreamingParser::from_reader(reader, &options)
+            .unwrap()
+            .unwrap();
+        let rows = collect_rows(&mut parser);
+
+        assert_eq!(rows.len(), PROBE_LINES * 2);
+        assert!(parser.error().unwrap().contains("corrupt input"));
+    }
+
+    #[test]
+    fn test_with_decoder() {
+        struct PairDecoder {
+            first: Option<String>,
+        }
+
+        impl RowDecoder for PairDecoder {
+            fn push_line(&mut self, line: &str) -> Vec<Vec<Option<String>>> {
+                match self.first.take() {
+                    Some(first) => vec![vec![Some(first), Some(line.to_string())]],
+                    None => {
+                        self.first = Some(line.to_string());
+                        Vec::new()
+                    }
+                }
+            }
+
+            fn finish(&mut self) -> Vec<Vec<Option<String>>> {
+                self.first
+                    .take()
+                    .map(|first| vec![Some(first)])
+                    .into_iter()
+                    .collect()
+            }
+        }
+
+        let mut parser = StreamingParser::with_decoder(
+            io::Cursor::new("key\nvalue\nlonely\n"),
+            vec!["key".to_string(), "value".to_string()],
+            Box::new(PairDecoder { first: None }),
+        )
+        .unwrap();
+
+        assert_eq!(
+            collect_rows(&mut parser),
+            vec![vec!["key", "value"], vec!["lonely"]]
+        );
+    }
+
+    #[test]
+    fn test_with_closure_decoder() {
+        let mut parser = StreamingParser::with_decoder(
+            io::Cursor::new("a=1\nskip\nb=2\n"),
+            vec!["key".to_string(), "value".to_string()],
+            Box::new(|line: &str| {
+                line.split_once('=')
+                    .map(|(k, v)| vec![k.to_string(), v.to_string()])
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(
+            collect_rows(&mut parser),
+            vec![vec!["a", "1"], vec!["b", "2"]]
+        );
+    }
+
+    #[test]
+    fn test_from_rows() {
+        let rows = (0..3).map(|i| Ok(vec![Some(i.to_string())]));
+        let mut parser = StreamingParser::from_rows(vec!["n".to_string()], rows);
+
+        assert_eq!(parser.headers(), vec!["n"]);
+        assert_eq!(
+            collect_rows(&mut parser),
+            vec![vec!["0"], vec!["1"], vec!["2"]]
+        );
+        assert_eq!(parser.total_rows_parsed(), 3);
+    }
+
+    #[test]
+    fn test_cancel_stops_endless_input() {
+        let rows = (0..).map(|i: u64| Ok(vec![Some(i.to_string())]));
+        let parser = StreamingParser::from_rows(vec!["n".to_string()], rows);
+
+        parser.cancel();
+        // Dropping joins the background thread; this returns only if it stopped
+        drop(parser);
+    }
+
+    #[test]
+    fn test_try_recv_batch_keeps_rest_of_batch() {
+        let rows = (0..BATCH_SIZE + 10).map(|i| Ok(vec![Some(i.to_string())]));
+        let mut parser = StreamingParser::from_rows(vec!["n".to_string()], rows);
+
+        let mut received = Vec::new();
+        while !parser.is_drained() {
+            let batch = parser.try_recv_batch(7);
+            assert!(batch.len() <= 7);
+            received.extend(batch);
+            assert_eq!(parser.rows_consumed(), received.len());
+        }
+
+        let expected: Vec<Vec<Option<String>>> = (0..BATCH_SIZE + 10)
+            .map(|i| vec![Some(i.to_string())])
+            .collect();
+        assert_eq!(received, expected);
+        assert_eq!(parser.rows_queued(), 0);
+    }
+
+    #[test]
+    fn test_backpressure_bounds_queued_rows() {
+        let rows = (0..).map(|i: u64| Ok(vec![Some(i.to_string())]));
+        let mut parser = StreamingParser::from_rows(vec!["n".to_string()], rows);
+        let limit = (MAX_QUEUED_BATCHES + 1) * BATCH_SIZE;
+
+        // The background thread fills the channel, then blocks
+        assert!(wait_for_queue_to_settle(&parser) <= limit);
+
+        // Receiving frees space and reading resumes
+        let wanted = BATCH_SIZE * 3;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut received = 0;
+        while received < wanted && Instant::now() < deadline {
+            let batch = parser.try_recv_batch(wanted - received);
+            if batch.is_empty() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            received += batch.len();
+        }
+        assert_eq!(received, wanted);
+        assert!(wait_for_queue_to_settle(&parser) <= limit);
+        assert!(parser.total_rows_parsed() <= received + limit);
+        assert!(!parser.is_complete());
+    }
+}
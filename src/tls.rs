@@ -0,0 +1,541 @@
+//! TLS settings for PostgreSQL connections.
+//!
+//! The postgres crate only understands `sslmode=disable|prefer|require`, so the
+//! libpq TLS parameters (`sslmode`, `sslrootcert`, `sslcert`, `sslkey`) are taken
+//! out of the connection string here and turned into a rustls client config.
+//! Defaults follow libpq: `sslmode=prefer`, and certificates are looked up in
+//! `~/.postgresql/` (`root.crt`, `postgresql.crt`, `postgresql.key`).
+
+use crate::conninfo::ConnParams;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{self, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{
+    CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// `sslrootcert` value that selects the operating system's trusted roots.
+const SYSTEM_ROOTS: &str = "system";
+
+/// How hard to insist on TLS, with libpq's meaning for each mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SslMode {
+    /// Never use TLS
+    Disable,
+    /// Try without TLS first, then with TLS
+    Allow,
+    /// Use TLS if the server supports it
+    #[default]
+    Prefer,
+    /// Always use TLS
+    Require,
+    /// Use TLS and check the server certificate is signed by a trusted root
+    VerifyCa,
+    /// Like `VerifyCa`, and check the certificate matches the host name
+    VerifyFull,
+}
+
+impl SslMode {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "disable" => Ok(SslMode::Disable),
+            "allow" => Ok(SslMode::Allow),
+            "prefer" => Ok(SslMode::Prefer),
+            "require" => Ok(SslMode::Require),
+            "verify-ca" => Ok(SslMode::VerifyCa),
+            "verify-full" => Ok(SslMode::VerifyFull),
+            _ => Err(format!(
+                "invalid sslmode \"{}\" (expected disable, allow, prefer, require, verify-ca or verify-full)",
+                value
+            )),
+        }
+    }
+
+    /// Check if the server certificate must be verified.
+    fn verifies(self) -> bool {
+        matches!(self, SslMode::VerifyCa | SslMode::VerifyFull)
+    }
+}
+
+impl fmt::Display for SslMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SslMode::Disable => "disable",
+            SslMode::Allow => "allow",
+            SslMode::Prefer => "prefer",
+            SslMode::Require => "require",
+            SslMode::VerifyCa => "verify-ca",
+            SslMode::VerifyFull => "verify-full",
+        })
+    }
+}
+
+/// TLS parameters from a connection string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsOptions {
+    pub mode: SslMode,
+    /// `sslrootcert`: trusted roots (PEM file, or `system`)
+    pub root_cert: Option<String>,
+    /// `sslcert`: client certificate (PEM file)
+    pub client_cert: Option<String>,
+    /// `sslkey`: client private key (PEM file)
+    pub client_key: Option<String>,
+}
+
+impl TlsOptions {
+    /// The mode to give the postgres crate, which only knows disable/prefer/require.
+    pub fn postgres_ssl_mode(&self) -> postgres::config::SslMode {
+        match self.mode {
+            SslMode::Disable | SslMode::Allow => postgres::config::SslMode::Disable,
+            SslMode::Prefer => postgres::config::SslMode::Prefer,
+            SslMode::Require | SslMode::VerifyCa | SslMode::VerifyFull => {
+                postgres::config::SslMode::Require
+            }
+        }
+    }
+
+    /// Build the rustls config for these options.
+    ///
+    /// Like libpq, `prefer` and `require` check the certificate chain (but not
+    /// the host name) when a root certificate file is available, and skip
+    /// verification otherwise.
+    pub fn client_config(&self) -> Result<ClientConfig, String> {
+        let provider = Arc::new(crypto::ring::default_provider());
+        let verifier: Arc<dyn ServerCertVerifier> = match self.root_store()? {
+            Some(roots) => {
+                let webpki =
+                    WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                        .build()
+                        .map_err(|e| format!("invalid root certificate: {}", e))?;
+                if self.mode == SslMode::VerifyFull {
+                    webpki
+                } else {
+                    Arc::new(CaOnlyVerifier(webpki))
+                }
+            }
+            None => Arc::new(NoVerifier(provider.clone())),
+        };
+
+        let builder = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .dangerous()
+            .with_custom_certificate_verifier(verifier);
+        match self.client_identity()? {
+            Some((certs, key)) => builder
+                .with_client_auth_cert(certs, key)
+                .map_err(|e| format!("invalid client certificate or key: {}", e)),
+            None => Ok(builder.with_no_client_auth()),
+        }
+    }
+
+    /// Trusted roots, or `None` if the server certificate is not checked.
+    fn root_store(&self) -> Result<Option<RootCertStore>, String> {
+        let path = match &self.root_cert {
+            Some(root) if root == SYSTEM_ROOTS => {
+                let mut roots = RootCertStore::empty();
+                let native = rustls_native_certs::load_native_certs();
+                roots.add_parsable_certificates(native.certs);
+                if roots.is_empty() {
+                    return Err("no trusted root certificates found on this system".to_string());
+                }
+                return Ok(Some(roots));
+            }
+            Some(root) => PathBuf::from(root),
+            None => match default_file("root.crt") {
+                Some(path) if path.exists() => path,
+                path if self.mode.verifies() => {
+                    let path = path.map_or_else(
+                        || "~/.postgresql/root.crt".to_string(),
+                        |p| p.display().to_string(),
+                    );
+                    return Err(format!(
+                        "root certificate file \"{}\" does not exist; set sslrootcert to a CA file \
+                         (or sslrootcert=system), or use sslmode=require to skip verification",
+                        path
+                    ));
+                }
+                _ => return Ok(None),
+            },
+        };
+
+        let mut roots = RootCertStore::empty();
+        for cert in read_pem_certs(&path)? {
+            roots.add(cert).map_err(|e| {
+                format!("invalid root certificate in \"{}\": {}", path.display(), e)
+            })?;
+        }
+        Ok(Some(roots))
+    }
+
+    /// Client certificate chain and key, if any.
+    ///
+    /// An explicit `sslcert` must exist; the default file is used only if present.
+    fn client_identity(
+        &self,
+    ) -> Result<Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>, String> {
+        let cert_path = match &self.client_cert {
+            Some(path) => PathBuf::from(path),
+            None => match default_file("postgresql.crt") {
+                Some(path) if path.exists() => path,
+                _ => return Ok(None),
+            },
+        };
+        let key_path = match &self.client_key {
+            Some(path) => PathBuf::from(path),
+            None => default_file("postgresql.key")
+                .ok_or_else(|| "sslcert is set but sslkey is not".to_string())?,
+        };
+
+        let certs = read_pem_certs(&cert_path)?;
+        let key = PrivateKeyDer::from_pem_file(&key_path).map_err(|e| {
+            format!(
+                "could not read private key file \"{}\": {}",
+                key_path.display(),
+                e
+            )
+        })?;
+        Ok(Some((certs, key)))
+    }
+}
+
+/// Path of a file in libpq's default certificate directory.
+fn default_file(name: &str) -> Option<PathBuf> {
+    #[cfg(windows)]
+    let dir = std::env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("postgresql"));
+    #[cfg(not(windows))]
+    let dir = std::env::var_os("HOME").map(|dir| PathBuf::from(dir).join(".postgresql"));
+    dir.map(|dir| dir.join(name))
+}
+
+fn read_pem_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let read_error = |e: rustls::pki_types::pem::Error| {
+        format!(
+            "could not read certificate file \"{}\": {}",
+            path.display(),
+            e
+        )
+    };
+    let certs = CertificateDer::pem_file_iter(path)
+        .map_err(read_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_error)?;
+    if certs.is_empty() {
+        return Err(format!("no certificates found in \"{}\"", path.display()));
+    }
+    Ok(certs)
+}
+
+/// Split the TLS parameters out of a connection string.
+///
+/// Accepts both key=value strings and `postgresql://` URIs. Returns a key=value
+/// connection string without the TLS parameters (for the postgres crate) and
+/// the parameters themselves.
+pub fn split_tls_params(connection_string: &str) -> Result<(String, TlsOptions), String> {
+    let mut params = ConnParams::parse(connection_string)?;
+    let options = TlsOptions {
+        mode: match params.remove("sslmode") {
+            Some(mode) => SslMode::parse(&mode)?,
+            None => SslMode::default(),
+        },
+        root_cert: params.remove("sslrootcert"),
+        client_cert: params.remove("sslcert"),
+        client_key: params.remove("sslkey"),
+    };
+    Ok((params.to_connection_string(), options))
+}
+
+/// Turn a TLS failure into a message saying what to check.
+///
+/// Returns `None` if `error` is not a TLS error.
+pub fn describe_tls_error(
+    error: &(dyn std::error::Error + 'static),
+    mode: SslMode,
+) -> Option<String> {
+    let mut source = Some(error);
+    while let Some(err) = source {
+        // tokio-rustls reports rustls errors inside io::Error
+        let rustls_error = err.downcast_ref::<rustls::Error>().or_else(|| {
+            err.downcast_ref::<std::io::Error>()
+                .and_then(|e| e.get_ref())
+                .and_then(|e| e.downcast_ref::<rustls::Error>())
+        });
+        if let Some(rustls_error) = rustls_error {
+            return Some(match rustls_error {
+                rustls::Error::InvalidCertificate(cert_error) => {
+                    let (reason, hint) = describe_certificate_error(cert_error);
+                    format!(
+                        "server certificate verification failed (sslmode={}): {}\n{}",
+                        mode, reason, hint
+                    )
+                }
+                other => format!("TLS handshake failed: {}", other),
+            });
+        }
+        source = err.source();
+    }
+    None
+}
+
+/// What went wrong with the certificate, and what to try next.
+fn describe_certificate_error(error: &CertificateError) -> (String, &'static str) {
+    const SKIP_VERIFY: &str = "Use sslmode=require to connect without verifying the certificate.";
+    match error {
+        CertificateError::UnknownIssuer => (
+            "the certificate is not signed by a trusted root certificate".to_string(),
+            "Set sslrootcert to the CA file that signed the server certificate.",
+        ),
+        CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. } => (
+            rustls::Error::InvalidCertificate(error.clone())
+                .to_string()
+                .trim_start_matches("invalid peer certificate: ")
+                .to_string(),
+            "Connect with a host name listed in the certificate, or use sslmode=verify-ca to skip the host name check.",
+        ),
+        CertificateError::Expired | CertificateError::ExpiredContext { .. } => {
+            ("the certificate has expired".to_string(), SKIP_VERIFY)
+        }
+        CertificateError::NotValidYet | CertificateError::NotValidYetContext { .. } => {
+            ("the certificate is not valid yet".to_string(), SKIP_VERIFY)
+        }
+        other => (
+            rustls::Error::InvalidCertificate(other.clone()).to_string(),
+            SKIP_VERIFY,
+        ),
+    }
+}
+
+/// Client config for cancel requests, which skips certificate verification.
+///
+/// A cancel request only carries the backend's key, and the server has
+/// already been verified when the connection was made.
+pub fn cancel_client_config() -> ClientConfig {
+    let provider = Arc::new(crypto::ring::default_provider());
+    ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .expect("ring supports the default protocol versions")
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(NoVerifier(provider)))
+        .with_no_client_auth()
+}
+
+/// Checks the certificate chain but not the host name (`verify-ca`).
+#[derive(Debug)]
+struct CaOnlyVerifier(Arc<WebPkiServerVerifier>);
+
+impl ServerCertVerifier for CaOnlyVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        // The chain is checked before the name, so a name error means a valid chain
+        match self
+            .0
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+        {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. },
+            )) => Ok(ServerCertVerified::assertion()),
+            result => result,
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_verify_schemes()
+    }
+}
+
+/// Accepts any server certificate (`prefer`/`require` without a root certificate).
+///
+/// The connection is still encrypted, and handshake signatures are still checked.
+#[derive(Debug)]
+struct NoVerifier(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_key_value_params() {
+        let (rest, options) = split_tls_params(
+            "host=db.example.com sslmode=verify-full sslrootcert='/etc/ssl/my ca.pem' user=app",
+        )
+        .unwrap();
+
+        assert_eq!(rest, "host='db.example.com' user='app'");
+        assert_eq!(options.mode, SslMode::VerifyFull);
+        assert_eq!(options.root_cert.as_deref(), Some("/etc/ssl/my ca.pem"));
+        assert_eq!(options.client_cert, None);
+    }
+
+    #[test]
+    fn test_split_keeps_quoted_values() {
+        let (rest, options) =
+            split_tls_params("password = 'it\\'s a secret' sslcert=a.crt sslkey = b.key").unwrap();
+
+        assert_eq!(rest, "password='it\\'s a secret'");
+        assert_eq!(options.mode, SslMode::Prefer);
+        assert_eq!(options.client_cert.as_deref(), Some("a.crt"));
+        assert_eq!(options.client_key.as_deref(), Some("b.key"));
+        // The rest still parses
+        let config: postgres::Config = rest.parse().unwrap();
+        assert_eq!(config.get_password(), Some(&b"it's a secret"[..]));
+    }
+
+    #[test]
+    fn test_split_uri_params() {
+        let (rest, options) = split_tls_params(
+            "postgresql://app@db.example.com/prod?sslmode=verify-ca&connect_timeout=5&sslrootcert=%2Ftmp%2Fca.pem",
+        )
+        .unwrap();
+
+        assert_eq!(
+            rest,
+            "user='app' host='db.example.com' dbname='prod' connect_timeout='5'"
+        );
+        assert_eq!(options.mode, SslMode::VerifyCa);
+        assert_eq!(options.root_cert.as_deref(), Some("/tmp/ca.pem"));
+
+        let (rest, options) = split_tls_params("postgres://localhost/db?sslmode=disable").unwrap();
+        assert_eq!(rest, "host='localhost' dbname='db'");
+        assert_eq!(options.mode, SslMode::Disable);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let err = split_tls_params("host=localhost sslmode=on").unwrap_err();
+        assert!(err.contains("invalid sslmode \"on\""));
+        assert!(split_tls_params("host=localhost user").is_err());
+        assert!(split_tls_params("password='oops").is_err());
+    }
+
+    #[test]
+    fn test_postgres_ssl_mode() {
+        let mode = |mode| TlsOptions {
+            mode,
+            ..TlsOptions::default()
+        };
+        assert_eq!(
+            mode(SslMode::VerifyFull).postgres_ssl_mode(),
+            postgres::config::SslMode::Require
+        );
+        assert_eq!(
+            mode(SslMode::Prefer).postgres_ssl_mode(),
+            postgres::config::SslMode::Prefer
+        );
+        assert_eq!(
+            mode(SslMode::Allow).postgres_ssl_mode(),
+            postgres::config::SslMode::Disable
+        );
+    }
+
+    #[test]
+    fn test_verify_needs_root_certificate() {
+        let options = TlsOptions {
+            mode: SslMode::VerifyFull,
+            root_cert: Some("/nonexistent/root.crt".to_string()),
+            ..TlsOptions::default()
+        };
+        let err = options.client_config().unwrap_err();
+        assert!(err.contains("could not read certificate file \"/nonexistent/root.crt\""));
+
+        // Without verification, no root certificate is needed
+        let options = TlsOptions {
+            mode: SslMode::Require,
+            root_cert: None,
+            ..TlsOptions::default()
+        };
+        if default_file("root.crt").is_none_or(|path| !path.exists()) {
+            assert!(options.client_config().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_describe_certificate_error() {
+        let error = rustls::Error::InvalidCertificate(CertificateError::UnknownIssuer);
+        let message = describe_tls_error(&error, SslMode::VerifyFull).unwrap();
+        assert!(message.starts_with(
+            "server certificate verification failed (sslmode=verify-full): \
+             the certificate is not signed by a trusted root certificate"
+        ));
+
+        // Wrapped the way tokio-rustls reports it
+        let io_error = std::io::Error::new(std::io::ErrorKind::InvalidData, error);
+        assert!(describe_tls_error(&io_error, SslMode::VerifyCa).is_some());
+
+        let other = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+        assert_eq!(describe_tls_error(&other, SslMode::Prefer), None);
+    }
+}
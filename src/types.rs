@@ -0,0 +1,421 @@
+//! Column types for loaded tables.
+//!
+//! Cells are stored as interned strings; a column's type is either declared
+//! by the source (PostgreSQL column types) or inferred from its values. Inference
+//! is incremental so streamed rows refine the types as they arrive.
+
+use lasso::{Rodeo, Spur};
+
+use crate::parser::null_cell;
+
+/// Type of a table column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    /// Whole numbers (`42`, `-7`)
+    Integer,
+    /// Fractional or exponent numbers (`3.14`, `1e-3`)
+    Decimal,
+    /// `true`/`false` (psql prints `t`/`f`)
+    Boolean,
+    /// ISO 8601 date (`2024-01-31`)
+    Date,
+    /// ISO 8601 date and time, optionally with fraction and offset
+    Timestamp,
+    /// Hyphenated UUID
+    Uuid,
+    /// JSON object or array
+    Json,
+    /// Anything else
+    Text,
+}
+
+impl ColumnType {
+    /// Infer the type of a single value.
+    ///
+    /// Returns `None` for empty values, which say nothing about the column.
+    pub fn of_value(value: &str) -> Option<Self> {
+        if value.is_empty() {
+            return None;
+        }
+        let value_type = if is_integer(value) {
+            ColumnType::Integer
+        } else if is_decimal(value) {
+            ColumnType::Decimal
+        } else if is_boolean(value) {
+            ColumnType::Boolean
+        } else if is_date(value) {
+            ColumnType::Date
+        } else if is_timestamp(value) {
+            ColumnType::Timestamp
+        } else if is_uuid(value) {
+            ColumnType::Uuid
+        } else if is_json(value) {
+            ColumnType::Json
+        } else {
+            ColumnType::Text
+        };
+        Some(value_type)
+    }
+
+    /// Narrowest type that holds values of both types.
+    pub fn merge(self, other: Self) -> Self {
+        use ColumnType::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Integer, Decimal) | (Decimal, Integer) => Decimal,
+            (Date, Timestamp) | (Timestamp, Date) => Timestamp,
+            _ => Text,
+        }
+    }
+
+    /// Map a PostgreSQL type name (`int4`, `timestamptz`, ...) to a column type.
+    pub fn from_pg_type_name(name: &str) -> Self {
+        match name {
+            "int2" | "int4" | "int8" | "oid" => ColumnType::Integer,
+            "numeric" | "float4" | "float8" => ColumnType::Decimal,
+            "bool" => ColumnType::Boolean,
+            "date" => ColumnType::Date,
+            "timestamp" | "timestamptz" => ColumnType::Timestamp,
+            "uuid" => ColumnType::Uuid,
+            "json" | "jsonb" => ColumnType::Json,
+            _ => ColumnType::Text,
+        }
+    }
+
+    /// Check if values of this type are numbers (rendered right-aligned).
+    pub fn is_numeric(self) -> bool {
+        matches!(self, ColumnType::Integer | ColumnType::Decimal)
+    }
+
+    /// Short name for display.
+    pub fn label(self) -> &'static str {
+        match self {
+            ColumnType::Integer => "integer",
+            ColumnType::Decimal => "decimal",
+            ColumnType::Boolean => "boolean",
+            ColumnType::Date => "date",
+            ColumnType::Timestamp => "timestamp",
+            ColumnType::Uuid => "uuid",
+            ColumnType::Json => "json",
+            ColumnType::Text => "text",
+        }
+    }
+}
+
+/// Per-column types of a table.
+///
+/// Columns without a known type (no non-NULL values seen yet) report `Text`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColumnTypes {
+    /// Type of each column, `None` until a value has been seen
+    types: Vec<Option<ColumnType>>,
+    /// Types were declared by the source and are not inferred
+    declared: bool,
+    /// Number of rows already inferred from
+    rows_scanned: usize,
+    /// Type names as declared by the source (e.g., `varchar`, `int4`)
+    type_names: Vec<String>,
+}
+
+impl ColumnTypes {
+    /// Types declared by the source (e.g., PostgreSQL result columns).
+    pub fn declared(types: Vec<ColumnType>) -> Self {
+        ColumnTypes {
+            types: types.into_iter().map(Some).collect(),
+            declared: true,
+            rows_scanned: 0,
+            type_names: Vec::new(),
+        }
+    }
+
+    /// Keep the source's own type names for display alongside the types.
+    pub fn with_type_names(mut self, type_names: Vec<String>) -> Self {
+        self.type_names = type_names;
+        self
+    }
+
+    /// Type name of a column: the source's name if known, otherwise the type's label.
+    pub fn type_name(&self, col: usize) -> &str {
+        self.type_names
+            .get(col)
+            .map(String::as_str)
+            .unwrap_or_else(|| self.get(col).label())
+    }
+
+    /// Type of a column (`Text` when unknown or out of range).
+    pub fn get(&self, col: usize) -> ColumnType {
+        self.types
+            .get(col)
+            .copied()
+            .flatten()
+            .unwrap_or(ColumnType::Text)
+    }
+
+    /// Check if the types were declared by the source rather than inferred.
+    pub fn is_declared(&self) -> bool {
+        self.declared
+    }
+
+    /// Refine inferred types with rows added since the last call.
+    ///
+    /// Columns that are already `Text` are not rescanned, so repeated calls while
+    /// streaming only look at new rows. Declared types are left unchanged.
+    pub fn infer(&mut self, column_count: usize, rows: &[Vec<Spur>], interner: &Rodeo) {
+        if self.types.len() < column_count {
+            self.types.resize(column_count, None);
+            // Existing rows may already hold cells for the new columns
+            self.rows_scanned = 0;
+        }
+        if self.declared {
+            return;
+        }
+        for row in rows.iter().skip(self.rows_scanned) {
+            for (column, spur) in self.types.iter_mut().zip(row) {
+                // NULL cells say nothing about the column either
+                if *column == Some(ColumnType::Text) || *spur == null_cell() {
+                    continue;
+                }
+                if let Some(value_type) = ColumnType::of_value(interner.resolve(spur)) {
+                    *column = Some(column.map_or(value_type, |t| t.merge(value_type)));
+                }
+            }
+        }
+        self.rows_scanned = rows.len();
+    }
+}
+
+fn strip_sign(value: &str) -> &str {
+    value
+        .strip_prefix('-')
+        .or_else(|| value.strip_prefix('+'))
+        .unwrap_or(value)
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Integers with leading zeros (`007`, zip codes) are treated as text.
+fn is_integer(value: &str) -> bool {
+    let digits = strip_sign(value);
+    is_digits(digits) && (digits.len() == 1 || !digits.starts_with('0'))
+}
+
+fn is_decimal(value: &str) -> bool {
+    let unsigned = strip_sign(value);
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(pos) => (&unsigned[..pos], Some(strip_sign(&unsigned[pos + 1..]))),
+        None => (unsigned, None),
+    };
+    if exponent.is_some_and(|exp| !is_digits(exp)) {
+        return false;
+    }
+    match mantissa.split_once('.') {
+        Some((whole, fraction)) => {
+            (whole.is_empty() || is_digits(whole))
+                && (fraction.is_empty() || is_digits(fraction))
+                && !(whole.is_empty() && fraction.is_empty())
+        }
+        None => exponent.is_some() && is_digits(mantissa),
+    }
+}
+
+fn is_boolean(value: &str) -> bool {
+    matches!(value, "t" | "f")
+        || value.eq_ignore_ascii_case("true")
+        || value.eq_ignore_ascii_case("false")
+}
+
+/// `YYYY-MM-DD` with a plausible month and day.
+fn is_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return false;
+    }
+    let (year, month, day) = (&value[..4], &value[5..7], &value[8..]);
+    is_digits(year)
+        && is_digits(month)
+        && is_digits(day)
+        && (1..=12).contains(&month.parse::<u8>().unwrap_or(0))
+        && (1..=31).contains(&day.parse::<u8>().unwrap_or(0))
+}
+
+/// `HH:MM[:SS[.fraction]]`
+fn is_time(value: &str) -> bool {
+    let mut parts = value.splitn(3, ':');
+    let (Some(hours), Some(minutes)) = (parts.next(), parts.next()) else {
+        return false;
+    };
+    let two_digits = |part: &str| part.len() == 2 && is_digits(part);
+    let seconds_ok = match parts.next() {
+        Some(seconds) => match seconds.split_once('.') {
+            Some((whole, fraction)) => two_digits(whole) && is_digits(fraction),
+            None => two_digits(seconds),
+        },
+        None => true,
+    };
+    two_digits(hours) && two_digits(minutes) && seconds_ok
+}
+
+/// Date and time separated by `T` or a space, with an optional `Z` or `±HH[:MM]` offset.
+fn is_timestamp(value: &str) -> bool {
+    if value.len() < 16 || !value.is_char_boundary(10) || !is_date(&value[..10]) {
+        return false;
+    }
+    if !matches!(value.as_bytes()[10], b'T' | b' ') {
+        return false;
+    }
+    let time = &value[11..];
+    let time = time.strip_suffix('Z').unwrap_or(time);
+    let time = match time.rfind(['+', '-']) {
+        Some(pos) => {
+            let offset = time[pos + 1..].replace(':', "");
+            if !is_digits(&offset) || !matches!(offset.len(), 2 | 4) {
+                return false;
+            }
+            &time[..pos]
+        }
+        None => time,
+    };
+    is_time(time)
+}
+
+/// `8-4-4-4-12` hexadecimal digits.
+fn is_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+fn is_json(value: &str) -> bool {
+    (value.starts_with('{') || value.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(value).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::intern_cell;
+
+    fn infer(values: &[&str]) -> ColumnType {
+        let cells: Vec<Option<&str>> = values.iter().copied().map(Some).collect();
+        infer_cells(&cells)
+    }
+
+    /// Infer a column's type from cells, with `None` for NULL.
+    fn infer_cells(values: &[Option<&str>]) -> ColumnType {
+        let mut interner = Rodeo::default();
+        let rows: Vec<Vec<Spur>> = values
+            .iter()
+            .map(|value| vec![intern_cell(&mut interner, *value)])
+            .collect();
+        let mut types = ColumnTypes::default();
+        types.infer(1, &rows, &interner);
+        types.get(0)
+    }
+
+    #[test]
+    fn test_of_value() {
+        assert_eq!(ColumnType::of_value("42"), Some(ColumnType::Integer));
+        assert_eq!(ColumnType::of_value("-7"), Some(ColumnType::Integer));
+        assert_eq!(ColumnType::of_value("3.14"), Some(ColumnType::Decimal));
+        assert_eq!(ColumnType::of_value("-1.5e10"), Some(ColumnType::Decimal));
+        assert_eq!(ColumnType::of_value("t"), Some(ColumnType::Boolean));
+        assert_eq!(ColumnType::of_value("FALSE"), Some(ColumnType::Boolean));
+        assert_eq!(ColumnType::of_value("2024-01-31"), Some(ColumnType::Date));
+        assert_eq!(
+            ColumnType::of_value("2024-01-31 12:30:00.123+02"),
+            Some(ColumnType::Timestamp)
+        );
+        assert_eq!(
+            ColumnType::of_value("2024-01-31T12:30:00Z"),
+            Some(ColumnType::Timestamp)
+        );
+        assert_eq!(
+            ColumnType::of_value("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"),
+            Some(ColumnType::Uuid)
+        );
+        assert_eq!(
+            ColumnType::of_value(r#"{"a": [1, 2]}"#),
+            Some(ColumnType::Json)
+        );
+        assert_eq!(ColumnType::of_value("hello"), Some(ColumnType::Text));
+        assert_eq!(ColumnType::of_value("007"), Some(ColumnType::Text));
+        assert_eq!(ColumnType::of_value("2024-13-01"), Some(ColumnType::Text));
+        assert_eq!(ColumnType::of_value("{not json"), Some(ColumnType::Text));
+        assert_eq!(ColumnType::of_value(""), None);
+        assert_eq!(ColumnType::of_value("\0"), Some(ColumnType::Text));
+    }
+
+    #[test]
+    fn test_infer_merges_values() {
+        assert_eq!(infer(&["1", "2", "3"]), ColumnType::Integer);
+        assert_eq!(infer(&["1", "2.5"]), ColumnType::Decimal);
+        assert_eq!(
+            infer(&["2024-01-31", "2024-02-01 08:00"]),
+            ColumnType::Timestamp
+        );
+        assert_eq!(infer(&["1", "t"]), ColumnType::Text);
+        // NULL and empty cells don't affect the type
+        assert_eq!(
+            infer_cells(&[Some(""), Some("1"), None]),
+            ColumnType::Integer
+        );
+        // A column without values is text
+        assert_eq!(infer_cells(&[None]), ColumnType::Text);
+    }
+
+    #[test]
+    fn test_infer_is_incremental() {
+        let mut interner = Rodeo::default();
+        let mut rows = vec![vec![interner.get_or_intern("1")]];
+        let mut types = ColumnTypes::default();
+        types.infer(1, &rows, &interner);
+        assert_eq!(types.get(0), ColumnType::Integer);
+
+        rows.push(vec![interner.get_or_intern("1.5")]);
+        types.infer(1, &rows, &interner);
+        assert_eq!(types.get(0), ColumnType::Decimal);
+
+        // New columns start unknown
+        types.infer(2, &rows, &interner);
+        assert_eq!(types.get(1), ColumnType::Text);
+    }
+
+    #[test]
+    fn test_declared_types_are_not_inferred() {
+        let mut interner = Rodeo::default();
+        let rows = vec![vec![interner.get_or_intern("abc")]];
+        let mut types = ColumnTypes::declared(vec![ColumnType::Integer]);
+        types.infer(1, &rows, &interner);
+        assert!(types.is_declared());
+        assert_eq!(types.get(0), ColumnType::Integer);
+    }
+
+    #[test]
+    fn test_type_name() {
+        let types = ColumnTypes::declared(vec![ColumnType::Text, ColumnType::Integer])
+            .with_type_names(vec!["varchar".to_string()]);
+        assert_eq!(types.type_name(0), "varchar");
+        assert_eq!(types.type_name(1), "integer");
+        assert_eq!(ColumnTypes::default().type_name(0), "text");
+    }
+
+    #[test]
+    fn test_from_pg_type_name() {
+        assert_eq!(ColumnType::from_pg_type_name("int8"), ColumnType::Integer);
+        assert_eq!(
+            ColumnType::from_pg_type_name("numeric"),
+            ColumnType::Decimal
+        );
+        assert_eq!(
+            ColumnType::from_pg_type_name("timestamptz"),
+            ColumnType::Timestamp
+        );
+        assert_eq!(ColumnType::from_pg_type_name("jsonb"), ColumnType::Json);
+        assert_eq!(ColumnType::from_pg_type_name("varchar"), ColumnType::Text);
+    }
+}
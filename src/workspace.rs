@@ -6,13 +6,16 @@
 use ratatui::widgets::TableState;
 
 use crate::column::ColumnConfig;
-use crate::parser::TableData;
+use crate::parser::{intern_cell, ParseIssue, TableData};
+use crate::plan::Plan;
+use crate::render::{cell_len, header_len};
+use crate::schema::SchemaTree;
 
 /// View mode for database browser.
 /// Determines what controls are shown and how navigation behaves.
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ViewMode {
-    TableList, // Viewing list of tables (can select with Enter)
+    TableList, // Browsing the schema tree (Enter opens a table)
     TableData, // Viewing table contents (Esc to go back)
     PipeData,  // Viewing piped data (no back navigation)
 }
@@ -40,6 +43,25 @@ pub struct Tab {
     pub cached_auto_widths: Vec<u16>,
     /// Number of rows scanned for cached widths
     pub widths_cached_for_rows: usize,
+    /// Filter, rows scanned and rows matching for the cached displayed row
+    /// count (see `count_displayed_rows`)
+    pub filtered_count_cache: Option<(String, usize, usize)>,
+    /// Keep the selection on the newest row as rows stream in (follow mode)
+    pub auto_scroll: bool,
+    /// Malformed input lines that were repaired or skipped while parsing
+    pub issues: Vec<ParseIssue>,
+    /// More rows can be fetched from the database cursor behind this tab
+    pub more_available: bool,
+    /// Schema browser tree whose visible nodes are the rows (TableList mode)
+    pub schema_tree: Option<SchemaTree>,
+    /// Schema and name of the relation the rows were opened from
+    pub relation: Option<(String, String)>,
+    /// SQL the rows came from (database results)
+    pub query: Option<String>,
+    /// Query plan whose visible nodes are the rows
+    pub plan: Option<Plan>,
+    /// Rows drawn highlighted, e.g. the most expensive plan nodes
+    pub highlighted_rows: Vec<usize>,
 }
 
 impl Tab {
@@ -57,19 +79,59 @@ impl Tab {
             view_mode,
             cached_auto_widths: Vec::new(),
             widths_cached_for_rows: 0,
+            filtered_count_cache: None,
+            auto_scroll: false,
+            issues: Vec::new(),
+            more_available: false,
+            schema_tree: None,
+            relation: None,
+            query: None,
+            plan: None,
+            highlighted_rows: Vec::new(),
         };
         tab.update_cached_widths();
         tab
     }
 
+    /// Create a schema browser tab showing the tree's visible nodes.
+    pub fn with_schema_tree(name: String, tree: SchemaTree) -> Self {
+        let mut tab = Self::new(name, tree.table_data(), ViewMode::TableList);
+        tab.schema_tree = Some(tree);
+        tab
+    }
+
+    /// Show the schema tree's visible nodes again after it changed.
+    pub fn refresh_schema_tree(&mut self) {
+        if let Some(ref tree) = self.schema_tree {
+            self.data = tree.table_data();
+            self.cached_auto_widths.clear();
+            self.widths_cached_for_rows = 0;
+            self.filtered_count_cache = None;
+            self.update_cached_widths();
+        }
+    }
+
+    /// Show the plan's visible nodes again after it changed, with the
+    /// expensive ones highlighted.
+    pub fn refresh_plan(&mut self) {
+        if let Some(ref plan) = self.plan {
+            self.data = plan.table_data();
+            self.highlighted_rows = plan.expensive_lines();
+            self.cached_auto_widths.clear();
+            self.widths_cached_for_rows = 0;
+            self.filtered_count_cache = None;
+            self.update_cached_widths();
+        }
+    }
+
     /// Incrementally update cached column widths.
     /// Only scans rows added since the last call.
     pub fn update_cached_widths(&mut self) {
         let num_cols = self.data.headers.len();
         if self.cached_auto_widths.is_empty() {
             self.cached_auto_widths = vec![0u16; num_cols];
-            for (i, header) in self.data.headers.iter().enumerate() {
-                self.cached_auto_widths[i] = (header.len() + 1) as u16;
+            for i in 0..num_cols {
+                self.cached_auto_widths[i] = (header_len(&self.data, i) + 1) as u16;
             }
         }
         let start = self.widths_cached_for_rows;
@@ -78,7 +140,7 @@ impl Tab {
             for row in &self.data.rows[start..end] {
                 for (i, cell) in row.iter().enumerate() {
                     if i < num_cols {
-                        let w = (self.data.resolve(cell).len() + 1) as u16;
+                        let w = (cell_len(self.data.value(cell)) + 1) as u16;
                         if w > self.cached_auto_widths[i] {
                             self.cached_auto_widths[i] = w;
                         }
@@ -89,16 +151,106 @@ impl Tab {
         }
     }
 
-    /// Intern and append string rows to the table.
+    /// Append columns discovered while streaming (e.g., new JSON keys).
+    /// `headers` must start with the tab's current headers.
+    pub fn extend_headers(&mut self, headers: Vec<String>) {
+        let old_count = self.data.headers.len();
+        if headers.len() <= old_count {
+            return;
+        }
+        self.column_config.grow(headers.len());
+        self.data.headers = headers;
+        self.data.infer_column_types();
+        for i in old_count..self.data.headers.len() {
+            self.cached_auto_widths
+                .push((header_len(&self.data, i) + 1) as u16);
+        }
+        // Rescan rows so existing cells in the new columns are measured
+        self.widths_cached_for_rows = 0;
+    }
+
+    /// Number of rows shown with the current filter applied.
+    pub fn displayed_row_count(&self) -> usize {
+        if self.filter_text.is_empty() {
+            return self.data.rows.len();
+        }
+        let filter_lower = self.filter_text.to_lowercase();
+        self.data
+            .rows
+            .iter()
+            .filter(|row| self.row_matches(row, &filter_lower))
+            .count()
+    }
+
+    /// Number of rows shown with the current filter applied, like
+    /// `displayed_row_count`. While the filter is unchanged, only rows added
+    /// since the last call are scanned, so tailing a stream stays cheap.
+    pub fn count_displayed_rows(&mut self) -> usize {
+        if self.filter_text.is_empty() {
+            return self.data.rows.len();
+        }
+        let (scanned, matched) = match self.filtered_count_cache {
+            Some((ref filter, scanned, matched))
+                if *filter == self.filter_text && scanned <= self.data.rows.len() =>
+            {
+                (scanned, matched)
+            }
+            _ => (0, 0),
+        };
+        let filter_lower = self.filter_text.to_lowercase();
+        let matched = matched
+            + self.data.rows[scanned..]
+                .iter()
+                .filter(|row| self.row_matches(row, &filter_lower))
+                .count();
+        self.filtered_count_cache = Some((self.filter_text.clone(), self.data.rows.len(), matched));
+        matched
+    }
+
+    /// Check if any cell of a row contains the (lowercased) filter text.
+    fn row_matches(&self, row: &[lasso::Spur], filter_lower: &str) -> bool {
+        row.iter().any(|cell| {
+            self.data
+                .resolve(cell)
+                .to_lowercase()
+                .contains(filter_lower)
+        })
+    }
+
+    /// Index into `data.rows` of the given displayed row (with the filter applied).
+    pub fn displayed_row_index(&self, displayed: usize) -> Option<usize> {
+        if self.filter_text.is_empty() {
+            return (displayed < self.data.rows.len()).then_some(displayed);
+        }
+        let filter_lower = self.filter_text.to_lowercase();
+        self.data
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| self.row_matches(row, &filter_lower))
+            .nth(displayed)
+            .map(|(idx, _)| idx)
+    }
+
+    /// Select the last displayed row (used to tail streaming input).
+    pub fn select_last_row(&mut self) {
+        let count = self.count_displayed_rows();
+        if count > 0 {
+            self.table_state.select(Some(count - 1));
+        }
+    }
+
+    /// Intern and append string rows (`None` cells are NULL) to the table.
     /// Used for streaming data load from background thread.
-    pub fn intern_and_append_rows(&mut self, string_rows: Vec<Vec<String>>) {
+    pub fn intern_and_append_rows(&mut self, string_rows: Vec<Vec<Option<String>>>) {
         for row in string_rows {
             let interned_row: Vec<lasso::Spur> = row
                 .iter()
-                .map(|cell| self.data.interner.get_or_intern(cell.as_str()))
+                .map(|cell| intern_cell(&mut self.data.interner, cell.as_deref()))
                 .collect();
             self.data.rows.push(interned_row);
         }
+        self.data.infer_column_types();
     }
 }
 
@@ -265,6 +417,7 @@ impl Default for Workspace {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::ColumnType;
 
     fn sample_data() -> TableData {
         use lasso::Rodeo;
@@ -273,11 +426,7 @@ mod tests {
             vec![interner.get_or_intern("1"), interner.get_or_intern("Alice")],
             vec![interner.get_or_intern("2"), interner.get_or_intern("Bob")],
         ];
-        TableData {
-            headers: vec!["id".to_string(), "name".to_string()],
-            rows,
-            interner,
-        }
+        TableData::new(vec!["id".to_string(), "name".to_string()], rows, interner)
     }
 
     #[test]
@@ -352,6 +501,53 @@ mod tests {
         assert_eq!(ws.active_idx, 0);
     }
 
+    #[test]
+    fn test_extend_headers() {
+        let mut tab = Tab::new("Test".to_string(), sample_data(), ViewMode::PipeData);
+        tab.extend_headers(vec!["id".into(), "name".into(), "email".into()]);
+
+        assert_eq!(tab.data.headers.len(), 3);
+        assert_eq!(tab.column_config.visible_indices(), vec![0, 1, 2]);
+        tab.update_cached_widths();
+        assert_eq!(tab.cached_auto_widths.len(), 3);
+    }
+
+    #[test]
+    fn test_appended_rows_refine_column_types() {
+        let mut tab = Tab::new("Test".to_string(), sample_data(), ViewMode::PipeData);
+        assert_eq!(tab.data.column_type(0), ColumnType::Integer);
+
+        tab.intern_and_append_rows(vec![vec![Some("3.5".into()), None]]);
+        assert_eq!(tab.data.column_type(0), ColumnType::Decimal);
+        assert_eq!(tab.data.column_type(1), ColumnType::Text);
+        assert!(tab.data.is_null(&tab.data.rows[2][1]));
+    }
+
+    #[test]
+    fn test_select_last_row_respects_filter() {
+        let mut tab = Tab::new("Test".to_string(), sample_data(), ViewMode::PipeData);
+        tab.select_last_row();
+        assert_eq!(tab.table_state.selected(), Some(1));
+
+        tab.filter_text = "alice".to_string();
+        assert_eq!(tab.displayed_row_count(), 1);
+        tab.select_last_row();
+        assert_eq!(tab.table_state.selected(), Some(0));
+
+        // Streamed rows are counted on top of the cached count
+        tab.intern_and_append_rows(vec![
+            vec![Some("3".into()), Some("Alice B".into())],
+            vec![Some("4".into()), Some("Dave".into())],
+        ]);
+        tab.select_last_row();
+        assert_eq!(tab.table_state.selected(), Some(1));
+        assert_eq!(tab.filtered_count_cache, Some(("alice".to_string(), 4, 2)));
+
+        // A new filter is counted from scratch
+        tab.filter_text = "dave".to_string();
+        assert_eq!(tab.count_displayed_rows(), 1);
+    }
+
     #[test]
     fn test_tab_initialization() {
         let data = sample_data();
@@ -242,9 +242,9 @@ fn test_column_visibility_with_export() {
     // Create TableData
     use lasso::Rodeo;
     let mut interner = Rodeo::default();
-    let data = TableData {
-        headers: vec!["id".into(), "name".into(), "age".into(), "city".into()],
-        rows: vec![
+    let data = TableData::new(
+        vec!["id".into(), "name".into(), "age".into(), "city".into()],
+        vec![
             vec![
                 interner.get_or_intern("1"),
                 interner.get_or_intern("Alice"),
@@ -259,7 +259,7 @@ fn test_column_visibility_with_export() {
             ],
         ],
         interner,
-    };
+    );
 
     // Create ColumnConfig and hide column 2 (age)
     let mut config = ColumnConfig::new(4);
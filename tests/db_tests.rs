@@ -0,0 +1,604 @@
+//! Integration tests for query results from a live PostgreSQL server.
+//!
+//! Set `PTE_TEST_DATABASE_URL` to a connection string (for example
+//! `host=localhost user=postgres`) to run them; they are skipped otherwise.
+
+use postgres::error::SqlState;
+use postgres::Client;
+use pretty_table_explorer::db::{
+    connect, describe_relation, error_message, execute_query, explain_query, load_columns,
+    load_foreign_keys, load_schema_tree, lookup_query, BackgroundFetch, BackgroundQuery,
+    QueryOutput, TableCursor,
+};
+use pretty_table_explorer::parser::TableData;
+use pretty_table_explorer::render::build_pane_render_data;
+use pretty_table_explorer::schema::{follow_foreign_keys, ObjectKind};
+use pretty_table_explorer::types::ColumnType;
+use pretty_table_explorer::workspace::{Tab, ViewMode};
+use std::time::Duration;
+
+fn test_client() -> Option<Client> {
+    let Ok(url) = std::env::var("PTE_TEST_DATABASE_URL") else {
+        eprintln!("PTE_TEST_DATABASE_URL not set, skipping");
+        return None;
+    };
+    let mut client = connect(&url).expect("failed to connect to test database");
+    client
+        .batch_execute("SET TimeZone = 'UTC'; SET IntervalStyle = 'postgres'")
+        .unwrap();
+    Some(client)
+}
+
+fn resolve_rows(data: &TableData) -> Vec<Vec<String>> {
+    data.rows.iter().map(|row| data.resolve_row(row)).collect()
+}
+
+fn column_types(data: &TableData) -> Vec<ColumnType> {
+    (0..data.column_count())
+        .map(|col| data.column_type(col))
+        .collect()
+}
+
+#[test]
+fn test_common_types_display_as_text() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    let data = execute_query(
+        &mut client,
+        "SELECT 12345678901234567890.123::numeric AS num,
+                1.5::real AS real,
+                '2024-01-31 12:30:00.5'::timestamp AS ts,
+                '2024-01-31 12:30:00+02'::timestamptz AS tstz,
+                '2024-01-31'::date AS day,
+                '12:30'::time AS clock,
+                'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'::uuid AS id,
+                '{\"a\": 1}'::json AS doc,
+                '{\"b\": [1, 2]}'::jsonb AS docb,
+                ARRAY[1, 2, 3] AS arr,
+                '1 day 02:00:00'::interval AS span,
+                '192.168.0.1/24'::inet AS addr,
+                '\\xdeadbeef'::bytea AS bin,
+                12.50::money AS price",
+    )
+    .unwrap();
+
+    assert_eq!(
+        data.headers,
+        vec![
+            "num", "real", "ts", "tstz", "day", "clock", "id", "doc", "docb", "arr", "span",
+            "addr", "bin", "price"
+        ]
+    );
+    assert_eq!(
+        resolve_rows(&data),
+        vec![vec![
+            "12345678901234567890.123",
+            "1.5",
+            "2024-01-31 12:30:00.5",
+            "2024-01-31 10:30:00+00",
+            "2024-01-31",
+            "12:30:00",
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+            "{\"a\": 1}",
+            "{\"b\": [1, 2]}",
+            "{1,2,3}",
+            "1 day 02:00:00",
+            "192.168.0.1/24",
+            "\\xdeadbeef",
+            "$12.50",
+        ]]
+    );
+}
+
+#[test]
+fn test_null_is_distinct_from_empty_string() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    let data = execute_query(
+        &mut client,
+        "SELECT NULL::numeric AS a, '' AS b, 'NULL' AS c, NULL::int[] AS d",
+    )
+    .unwrap();
+
+    let row = &data.rows[0];
+    assert!(data.is_null(&row[0]));
+    assert_eq!(data.value(&row[1]), Some(""));
+    assert_eq!(data.value(&row[2]), Some("NULL"));
+    assert!(data.is_null(&row[3]));
+}
+
+#[test]
+fn test_column_types_come_from_postgres() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    let data = execute_query(
+        &mut client,
+        "SELECT 1::int8 AS a, 2.5::numeric AS b, true AS c, now()::date AS d,
+                now() AS e, gen_random_uuid() AS f, '{}'::jsonb AS g, 'x'::text AS h,
+                '7'::text AS i",
+    )
+    .unwrap();
+
+    assert!(data.column_types.is_declared());
+    assert_eq!(
+        column_types(&data),
+        vec![
+            ColumnType::Integer,
+            ColumnType::Decimal,
+            ColumnType::Boolean,
+            ColumnType::Date,
+            ColumnType::Timestamp,
+            ColumnType::Uuid,
+            ColumnType::Json,
+            ColumnType::Text,
+            // Declared text stays text even if the values look numeric
+            ColumnType::Text,
+        ]
+    );
+}
+
+#[test]
+fn test_user_defined_types() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    client
+        .batch_execute(
+            "CREATE TEMP TABLE moods (m text);
+             DROP TYPE IF EXISTS pg_temp.mood;
+             CREATE TYPE pg_temp.mood AS ENUM ('sad', 'happy')",
+        )
+        .unwrap();
+    let data = execute_query(
+        &mut client,
+        "SELECT 'happy'::pg_temp.mood AS mood, int4range(1, 5) AS span",
+    )
+    .unwrap();
+
+    assert_eq!(resolve_rows(&data), vec![vec!["happy", "[1,5)"]]);
+}
+
+#[test]
+fn test_zero_rows_keep_headers() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    let data = execute_query(&mut client, "SELECT 1 AS a, 'x' AS b WHERE false").unwrap();
+
+    assert_eq!(data.headers, vec!["a", "b"]);
+    assert_eq!(data.row_count(), 0);
+    assert_eq!(
+        column_types(&data),
+        vec![ColumnType::Integer, ColumnType::Text]
+    );
+    assert_eq!(data.column_type_name(0), "int4");
+    assert_eq!(data.column_type_name(1), "text");
+
+    // The tab shows the type names under the headers
+    let tab = Tab::new("empty".to_string(), data, ViewMode::TableData);
+    let pane = build_pane_render_data(&tab, usize::MAX);
+    assert_eq!(
+        pane.header_types,
+        Some(vec!["int4".to_string(), "text".to_string()])
+    );
+}
+
+#[test]
+fn test_statement_without_result_set() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    let data = execute_query(&mut client, "SET application_name = 'pte_test'").unwrap();
+
+    assert!(data.headers.is_empty());
+    assert_eq!(data.row_count(), 0);
+}
+
+#[test]
+fn test_multiple_statements_show_last_result() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    let data = execute_query(&mut client, "SELECT 'first' AS a; SELECT 42 AS b").unwrap();
+
+    assert_eq!(data.headers, vec!["b"]);
+    assert_eq!(resolve_rows(&data), vec![vec!["42"]]);
+    // Types are inferred when the statements cannot be described
+    assert!(!data.column_types.is_declared());
+    assert_eq!(data.column_type(0), ColumnType::Integer);
+}
+
+#[test]
+fn test_query_error_is_returned() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    let err = execute_query(&mut client, "SELECT * FROM no_such_table").unwrap_err();
+    assert_eq!(
+        error_message(err.as_ref()),
+        "ERROR: relation \"no_such_table\" does not exist"
+    );
+    let err = err.downcast_ref::<postgres::Error>().unwrap();
+    assert_eq!(err.code(), Some(&SqlState::UNDEFINED_TABLE));
+}
+
+#[test]
+fn test_table_cursor_fetches_pages() {
+    let Ok(url) = std::env::var("PTE_TEST_DATABASE_URL") else {
+        return;
+    };
+    let (mut cursor, data) = TableCursor::open(
+        &url,
+        "SELECT n, n * 1.5 AS half FROM generate_series(1, 250) n",
+        100,
+    )
+    .unwrap();
+
+    assert_eq!(data.headers, vec!["n", "half"]);
+    assert_eq!(data.row_count(), 100);
+    assert_eq!(
+        column_types(&data),
+        vec![ColumnType::Integer, ColumnType::Decimal]
+    );
+    assert!(cursor.has_more());
+
+    // Pages append through the streaming path
+    let mut tab = Tab::new("series".to_string(), data, ViewMode::TableData);
+    tab.intern_and_append_rows(cursor.fetch_page().unwrap());
+    assert!(cursor.has_more());
+    tab.intern_and_append_rows(cursor.fetch_page().unwrap());
+    assert!(!cursor.has_more());
+    assert!(cursor.fetch_page().unwrap().is_empty());
+
+    assert_eq!(tab.data.row_count(), 250);
+    assert_eq!(
+        tab.data.resolve_row(&tab.data.rows[249]),
+        vec!["250", "375.0"]
+    );
+}
+
+#[test]
+fn test_table_cursor_reports_errors_when_opened() {
+    let Ok(url) = std::env::var("PTE_TEST_DATABASE_URL") else {
+        return;
+    };
+    // Row 150 divides by zero, past the first page; the held cursor reads
+    // the whole result when it is opened
+    let Err(err) = TableCursor::open(
+        &url,
+        "SELECT 1 / (n - 150) AS q FROM generate_series(1, 250) n",
+        100,
+    ) else {
+        panic!("opening the cursor should fail");
+    };
+    assert_eq!(
+        err.downcast_ref::<postgres::Error>().and_then(|e| e.code()),
+        Some(&SqlState::DIVISION_BY_ZERO)
+    );
+}
+
+#[test]
+fn test_table_cursor_is_not_idle_in_transaction() {
+    let Ok(url) = std::env::var("PTE_TEST_DATABASE_URL") else {
+        return;
+    };
+    // The server ends sessions left idle in a transaction after 100ms
+    let url = format!(
+        "{} options='-c idle_in_transaction_session_timeout=100'",
+        url
+    );
+    let (cursor, data) =
+        TableCursor::open(&url, "SELECT n FROM generate_series(1, 150) n", 100).unwrap();
+    assert_eq!(data.row_count(), 100);
+
+    std::thread::sleep(Duration::from_millis(300));
+    let fetch = BackgroundFetch::start(cursor);
+    let (cursor, rows) = fetch.finish();
+    assert_eq!(rows.unwrap().len(), 50);
+    assert!(!cursor.has_more());
+}
+
+#[test]
+fn test_table_cursor_without_rows() {
+    let Ok(url) = std::env::var("PTE_TEST_DATABASE_URL") else {
+        return;
+    };
+    let (cursor, data) = TableCursor::open(&url, "SELECT 1 AS a WHERE false", 100).unwrap();
+
+    assert_eq!(data.headers, vec!["a"]);
+    assert_eq!(data.row_count(), 0);
+    assert!(!cursor.has_more());
+}
+
+#[test]
+fn test_schema_tree_lists_other_schemas() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    client
+        .batch_execute(
+            "DROP SCHEMA IF EXISTS pte_browse CASCADE;
+             CREATE SCHEMA pte_browse;
+             CREATE TABLE pte_browse.orders (id int PRIMARY KEY, note varchar(40));
+             CREATE VIEW pte_browse.recent AS SELECT id FROM pte_browse.orders;
+             CREATE FUNCTION pte_browse.total(a int) RETURNS int AS 'SELECT a' LANGUAGE sql;",
+        )
+        .unwrap();
+
+    let tree = load_schema_tree(&mut client).unwrap();
+    let schema = tree
+        .schemas
+        .iter()
+        .find(|s| s.name == "pte_browse")
+        .expect("schema listed");
+    let objects: Vec<(&str, ObjectKind)> = schema
+        .objects
+        .iter()
+        .map(|o| (o.name.as_str(), o.kind))
+        .collect();
+    assert_eq!(
+        objects,
+        vec![
+            ("orders", ObjectKind::Table),
+            ("recent", ObjectKind::View),
+            ("total(a integer)", ObjectKind::Function),
+        ]
+    );
+    assert!(schema.objects[0].size.is_some());
+    assert_eq!(tree.schemas.last().unwrap().name, "pg_catalog");
+
+    let columns = load_columns(&mut client, "pte_browse", "orders").unwrap();
+    let columns: Vec<(&str, &str, bool)> = columns
+        .iter()
+        .map(|c| (c.name.as_str(), c.type_name.as_str(), c.not_null))
+        .collect();
+    assert_eq!(
+        columns,
+        vec![
+            ("id", "integer", true),
+            ("note", "character varying(40)", false)
+        ]
+    );
+
+    client
+        .batch_execute("DROP SCHEMA pte_browse CASCADE")
+        .unwrap();
+}
+
+#[test]
+fn test_describe_relation() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    client
+        .batch_execute(
+            "DROP SCHEMA IF EXISTS pte_describe CASCADE;
+             CREATE SCHEMA pte_describe;
+             CREATE TABLE pte_describe.customers (id int PRIMARY KEY);
+             CREATE TABLE pte_describe.orders (
+                 id serial PRIMARY KEY,
+                 customer_id int NOT NULL REFERENCES pte_describe.customers (id),
+                 total numeric CHECK (total >= 0)
+             );
+             COMMENT ON COLUMN pte_describe.orders.total IS 'gross amount';
+             CREATE INDEX orders_customer ON pte_describe.orders (customer_id);
+             CREATE FUNCTION pte_describe.noop() RETURNS trigger AS
+                 'BEGIN RETURN NEW; END' LANGUAGE plpgsql;
+             CREATE TRIGGER orders_noop BEFORE INSERT ON pte_describe.orders
+                 FOR EACH ROW EXECUTE FUNCTION pte_describe.noop();",
+        )
+        .unwrap();
+
+    let data = describe_relation(&mut client, "pte_describe", "orders").unwrap();
+    assert_eq!(
+        data.headers,
+        vec!["Section", "Name", "Definition", "Details", "Comment"]
+    );
+    let rows = resolve_rows(&data);
+    let find = |section: &str, name: &str| {
+        rows.iter()
+            .find(|row| row[0] == section && row[1] == name)
+            .unwrap_or_else(|| panic!("no {} {} in {:?}", section, name, rows))
+            .clone()
+    };
+
+    assert_eq!(
+        find("relation", "pte_describe.orders")[2..4],
+        ["table", "owner postgres"]
+    );
+    assert_eq!(
+        find("column", "id")[2..4],
+        [
+            "integer",
+            "not null, default nextval('pte_describe.orders_id_seq'::regclass)"
+        ]
+    );
+    assert_eq!(find("column", "customer_id")[3], "not null");
+    assert_eq!(find("column", "total")[4], "gross amount");
+    assert!(find("index", "orders_pkey")[3].starts_with("primary key, "));
+    assert_eq!(
+        find("index", "orders_customer")[2],
+        "CREATE INDEX orders_customer ON pte_describe.orders USING btree (customer_id)"
+    );
+    assert_eq!(
+        find("constraint", "orders_total_check")[2..4],
+        ["CHECK ((total >= (0)::numeric))", "check"]
+    );
+    assert_eq!(
+        find("foreign key", "orders_customer_id_fkey")[2],
+        "FOREIGN KEY (customer_id) REFERENCES pte_describe.customers(id)"
+    );
+    assert!(find("trigger", "orders_noop")[2].starts_with("CREATE TRIGGER orders_noop"));
+    assert!(rows.iter().any(|row| row[0] == "size" && row[1] == "total"));
+
+    // The parent lists the foreign key pointing at it
+    let parent = describe_relation(&mut client, "pte_describe", "customers").unwrap();
+    let parent_rows = resolve_rows(&parent);
+    assert!(parent_rows.iter().any(|row| row[0] == "referenced by"
+        && row[1] == "orders_customer_id_fkey"
+        && row[3] == "from pte_describe.orders"));
+
+    assert!(describe_relation(&mut client, "pte_describe", "missing").is_err());
+    client
+        .batch_execute("DROP SCHEMA pte_describe CASCADE")
+        .unwrap();
+}
+
+#[test]
+fn test_follow_foreign_keys() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    client
+        .batch_execute(
+            "DROP SCHEMA IF EXISTS pte_fk CASCADE;
+             CREATE SCHEMA pte_fk;
+             CREATE TABLE pte_fk.shops (shop text, no int, PRIMARY KEY (shop, no));
+             CREATE TABLE pte_fk.orders (
+                 id int PRIMARY KEY,
+                 shop text,
+                 shop_no int,
+                 FOREIGN KEY (shop, shop_no) REFERENCES pte_fk.shops (shop, no)
+             );
+             INSERT INTO pte_fk.shops VALUES ('O''Brien\\s', 1), ('other', 1);
+             INSERT INTO pte_fk.orders VALUES
+                 (10, 'O''Brien\\s', 1), (11, 'O''Brien\\s', 1), (12, 'other', 1);",
+        )
+        .unwrap();
+
+    let keys = load_foreign_keys(&mut client, "pte_fk", "orders").unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].columns, vec!["shop", "shop_no"]);
+    assert_eq!(keys[0].ref_columns, vec!["shop", "no"]);
+
+    // Forward from an order to its shop, with a value that needs quoting
+    let order = execute_query(&mut client, "SELECT * FROM pte_fk.orders WHERE id = 10").unwrap();
+    let row: Vec<(String, Option<String>)> = order
+        .headers
+        .iter()
+        .zip(&order.rows[0])
+        .map(|(header, spur)| (header.clone(), order.value(spur).map(str::to_string)))
+        .collect();
+    let lookups = follow_foreign_keys(&keys, ("pte_fk", "orders"), "shop_no", &row, false).unwrap();
+    let shop = execute_query(&mut client, &lookup_query(&lookups[0])).unwrap();
+    assert_eq!(resolve_rows(&shop), vec![vec!["O'Brien\\s", "1"]]);
+
+    // Back from the shop to both of its orders
+    let keys = load_foreign_keys(&mut client, "pte_fk", "shops").unwrap();
+    let row = vec![
+        ("shop".to_string(), Some("O'Brien\\s".to_string())),
+        ("no".to_string(), Some("1".to_string())),
+    ];
+    let lookups = follow_foreign_keys(&keys, ("pte_fk", "shops"), "shop", &row, true).unwrap();
+    let orders = execute_query(&mut client, &(lookup_query(&lookups[0]) + " ORDER BY id")).unwrap();
+    assert_eq!(resolve_rows(&orders).len(), 2);
+    assert_eq!(resolve_rows(&orders)[1][0], "11");
+
+    client.batch_execute("DROP SCHEMA pte_fk CASCADE").unwrap();
+}
+
+#[test]
+fn test_explain_query_rolls_back() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS pte_explain;
+             CREATE TABLE pte_explain (id int PRIMARY KEY, n int);
+             INSERT INTO pte_explain SELECT i, 0 FROM generate_series(1, 100) i;",
+        )
+        .unwrap();
+
+    let plan = explain_query(&mut client, "UPDATE pte_explain SET n = 1 WHERE id > 50;").unwrap();
+    assert!(plan.nodes[0].label.starts_with("Update on pte_explain"));
+    assert_eq!(plan.nodes[1].actual_rows, Some(50.0));
+    assert!(plan.execution_time.is_some());
+    assert_eq!(plan.table_data().headers[0], "Node");
+
+    // ANALYZE executed the update, but it was rolled back
+    let sum = execute_query(&mut client, "SELECT sum(n)::int FROM pte_explain").unwrap();
+    assert_eq!(resolve_rows(&sum), vec![vec!["0"]]);
+
+    // Only one statement is explained; a trailing one never runs
+    let err = explain_query(&mut client, "SELECT 1; COMMIT; DELETE FROM pte_explain").unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<postgres::Error>().and_then(|e| e.code()),
+        Some(&SqlState::SYNTAX_ERROR)
+    );
+    let count = execute_query(&mut client, "SELECT count(*)::int FROM pte_explain").unwrap();
+    assert_eq!(resolve_rows(&count), vec![vec!["100"]]);
+
+    let explained = BackgroundQuery::start_explain(client, "SELECT 1".to_string());
+    let (mut client, result) = explained.finish();
+    assert!(matches!(result, Ok(QueryOutput::Plan(_))));
+
+    client.batch_execute("DROP TABLE pte_explain").unwrap();
+}
+
+#[test]
+fn test_explain_query_keeps_open_transaction() {
+    let Some(mut client) = test_client() else {
+        return;
+    };
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS pte_explain_tx;
+             CREATE TABLE pte_explain_tx (id int PRIMARY KEY, n int);",
+        )
+        .unwrap();
+
+    // The user started a transaction at the prompt
+    execute_query(&mut client, "BEGIN").unwrap();
+    execute_query(&mut client, "INSERT INTO pte_explain_tx VALUES (1, 0)").unwrap();
+
+    let plan = explain_query(&mut client, "UPDATE pte_explain_tx SET n = 1").unwrap();
+    assert_eq!(plan.nodes[1].actual_rows, Some(1.0));
+    // A failed explain doesn't abort the transaction either
+    assert!(explain_query(&mut client, "SELECT 1 / 0").is_err());
+
+    // The insert is still there and the update was rolled back
+    let rows = execute_query(&mut client, "SELECT id, n FROM pte_explain_tx").unwrap();
+    assert_eq!(resolve_rows(&rows), vec![vec!["1", "0"]]);
+    execute_query(&mut client, "COMMIT").unwrap();
+    let count = execute_query(&mut client, "SELECT count(*)::int FROM pte_explain_tx").unwrap();
+    assert_eq!(resolve_rows(&count), vec![vec!["1"]]);
+
+    client.batch_execute("DROP TABLE pte_explain_tx").unwrap();
+}
+
+#[test]
+fn test_background_query_returns_client() {
+    let Some(client) = test_client() else {
+        return;
+    };
+    let query = BackgroundQuery::start(client, "SELECT 42 AS answer".to_string());
+    let (mut client, result) = query.finish();
+
+    let Ok(QueryOutput::Table(data)) = result else {
+        panic!("expected a table, got {:?}", result);
+    };
+    assert_eq!(resolve_rows(&data), vec![vec!["42"]]);
+    // The client is usable again
+    assert!(execute_query(&mut client, "SELECT 1").is_ok());
+}
+
+#[test]
+fn test_background_query_cancel() {
+    let Some(client) = test_client() else {
+        return;
+    };
+    let mut query = BackgroundQuery::start(client, "SELECT pg_sleep(30)".to_string());
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(!query.is_finished());
+
+    query.cancel();
+    assert!(query.is_cancelled());
+    let (mut client, result) = query.finish();
+
+    assert!(result.is_err());
+    assert!(execute_query(&mut client, "SELECT 1").is_ok());
+}
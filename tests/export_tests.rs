@@ -29,11 +29,11 @@ fn sample_table() -> TableData {
             interner.get_or_intern("35"),
         ],
     ];
-    TableData {
-        headers: vec!["id".into(), "name".into(), "age".into()],
+    TableData::new(
+        vec!["id".into(), "name".into(), "age".into()],
         rows,
         interner,
-    }
+    )
 }
 
 #[test]
@@ -161,9 +161,9 @@ fn test_export_csv_special_characters() {
     // Create data with commas, quotes, and newlines
     use lasso::Rodeo;
     let mut interner = Rodeo::default();
-    let data = TableData {
-        headers: vec!["id".into(), "description".into(), "notes".into()],
-        rows: vec![
+    let data = TableData::new(
+        vec!["id".into(), "description".into(), "notes".into()],
+        vec![
             vec![
                 interner.get_or_intern("1"),
                 interner.get_or_intern("Value with, comma"),
@@ -181,7 +181,7 @@ fn test_export_csv_special_characters() {
             ],
         ],
         interner,
-    };
+    );
     let visible = vec![0, 1, 2];
 
     let result = export_table(&data, &visible, ExportFormat::Csv).expect("CSV export failed");
@@ -209,11 +209,7 @@ fn test_export_csv_special_characters() {
 #[test]
 fn test_export_empty_table() {
     use lasso::Rodeo;
-    let data = TableData {
-        headers: vec!["col1".into(), "col2".into()],
-        rows: vec![],
-        interner: Rodeo::default(),
-    };
+    let data = TableData::new(vec!["col1".into(), "col2".into()], vec![], Rodeo::default());
     let visible = vec![0, 1];
 
     // CSV should have header only
@@ -277,9 +273,9 @@ fn test_export_single_column() {
 fn test_export_json_preserves_structure() {
     use lasso::Rodeo;
     let mut interner = Rodeo::default();
-    let data = TableData {
-        headers: vec!["user_id".into(), "username".into(), "score".into()],
-        rows: vec![
+    let data = TableData::new(
+        vec!["user_id".into(), "username".into(), "score".into()],
+        vec![
             vec![
                 interner.get_or_intern("100"),
                 interner.get_or_intern("player1"),
@@ -292,7 +288,7 @@ fn test_export_json_preserves_structure() {
             ],
         ],
         interner,
-    };
+    );
     let visible = vec![0, 1, 2];
 
     let result = export_table(&data, &visible, ExportFormat::Json).expect("JSON export failed");
@@ -0,0 +1,101 @@
+//! Integration tests for psql cell values containing pipes and wide characters.
+//!
+//! These tests verify that rows are sliced at the column boundaries of the
+//! `----+----` separator line, so literal `|` characters inside values and
+//! double-width Unicode do not shift the following columns.
+
+use pretty_table_explorer::parser::parse_psql;
+use pretty_table_explorer::render::build_pane_render_data;
+use pretty_table_explorer::workspace::{Tab, ViewMode};
+
+/// Sample psql output with pipes inside values (URLs, regexes, shell commands)
+const PIPE_PSQL_OUTPUT: &str = r#"
+ id |       command        |        pattern         | status
+----+----------------------+------------------------+--------
+  1 | ps aux | grep pte    | ^(foo|bar)$            | ok
+  2 | cat a.txt | sort     | https://x.io/?q=a|b    | failed
+  3 | ls                   | |leading and trailing| | ok
+(3 rows)
+"#;
+
+/// Sample psql output with double-width characters (CJK), including a pipe between them
+const WIDE_PSQL_OUTPUT: &str = r#"
+ id | name      | note
+----+-----------+------
+ 1  | 日本語    | a|b
+ 2  | 東京|大阪 | c
+ 3  | plain     | d
+(3 rows)
+"#;
+
+fn resolve_rows(tab: &Tab) -> Vec<Vec<String>> {
+    tab.data
+        .rows
+        .iter()
+        .map(|row| tab.data.resolve_row(row))
+        .collect()
+}
+
+#[test]
+fn test_embedded_pipes_keep_columns() {
+    let table_data = parse_psql(PIPE_PSQL_OUTPUT).expect("Failed to parse sample data");
+    assert_eq!(
+        table_data.headers,
+        vec!["id", "command", "pattern", "status"]
+    );
+
+    let tab = Tab::new("Test".to_string(), table_data, ViewMode::PipeData);
+    let rows = resolve_rows(&tab);
+
+    assert_eq!(rows.len(), 3);
+    for row in &rows {
+        assert_eq!(row.len(), 4, "Every row should have 4 cells: {:?}", row);
+    }
+    assert_eq!(rows[0], vec!["1", "ps aux | grep pte", "^(foo|bar)$", "ok"]);
+    assert_eq!(
+        rows[1],
+        vec!["2", "cat a.txt | sort", "https://x.io/?q=a|b", "failed"]
+    );
+    assert_eq!(rows[2], vec!["3", "ls", "|leading and trailing|", "ok"]);
+}
+
+#[test]
+fn test_filter_matches_value_with_pipe() {
+    let table_data = parse_psql(PIPE_PSQL_OUTPUT).expect("Failed to parse sample data");
+
+    let mut tab = Tab::new("Test".to_string(), table_data, ViewMode::PipeData);
+    tab.filter_text = "a|b".to_string();
+
+    let render_data = build_pane_render_data(&tab, usize::MAX);
+
+    assert_eq!(
+        render_data.displayed_row_count, 1,
+        "Should match the one row whose URL contains 'a|b'"
+    );
+    assert_eq!(render_data.display_rows[0][3], "failed");
+}
+
+#[test]
+fn test_wide_unicode_keeps_columns() {
+    let table_data = parse_psql(WIDE_PSQL_OUTPUT).expect("Failed to parse sample data");
+    assert_eq!(table_data.headers, vec!["id", "name", "note"]);
+
+    let tab = Tab::new("Test".to_string(), table_data, ViewMode::PipeData);
+    let rows = resolve_rows(&tab);
+
+    assert_eq!(rows[0], vec!["1", "日本語", "a|b"]);
+    assert_eq!(rows[1], vec!["2", "東京|大阪", "c"]);
+    assert_eq!(rows[2], vec!["3", "plain", "d"]);
+}
+
+#[test]
+fn test_misaligned_rows_fall_back_to_pipe_split() {
+    // Hand-written rows that do not line up with the separator still parse
+    let input = " a | b\n---+---\n1|2\n";
+
+    let table_data = parse_psql(input).expect("Failed to parse sample data");
+    assert_eq!(
+        table_data.resolve_row(&table_data.rows[0]),
+        vec!["1".to_string(), "2".to_string()]
+    );
+}
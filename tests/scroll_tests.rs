@@ -26,11 +26,7 @@ fn create_test_data(num_rows: usize, num_cols: usize) -> TableData {
                 .collect()
         })
         .collect();
-    TableData {
-        headers,
-        rows,
-        interner,
-    }
+    TableData::new(headers, rows, interner)
 }
 
 #[test]